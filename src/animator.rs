@@ -0,0 +1,90 @@
+use std::{
+  thread::sleep,
+  time::{Duration, Instant},
+};
+
+use linux_embedded_hal::i2cdev::{core::I2CDevice, linux::LinuxI2CError};
+
+use crate::LP55231;
+
+/// Host-driven animation at a fixed frame rate, bypassing the programming
+/// engines.
+///
+/// Each frame holds the PWM values for every [`Channel`](crate::Channel),
+/// ordered D1 through D9, and is pushed with [`LP55231::set_all_pwm`].
+///
+/// Example:
+/// ```no_run
+/// # use ti_lp55231::{Animator, LP55231};
+/// # let mut ic = LP55231::create("/dev/i2c-2", 0x32)?;
+/// // Fade all channels in over one second.
+/// let frames = (0..=255).map(|pwm| [pwm as u8; 9]);
+/// let report = Animator::new(255).run(&mut ic, frames)?;
+/// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+/// ```
+pub struct Animator {
+  frame_interval: Duration,
+}
+
+/// Outcome of an [`Animator`] run.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct AnimationReport {
+  /// Number of frames written to the IC.
+  pub frames_pushed: usize,
+  /// Number of frames skipped because their time slot had already passed
+  /// (i.e. the bus couldn't keep up with the frame rate).
+  pub frames_dropped: usize,
+}
+
+impl Animator {
+  /// Create an animator pushing `fps` frames per second.
+  ///
+  /// Panics if `fps` is 0.
+  pub fn new(fps: u32) -> Self {
+    assert!(fps > 0, "fps must be greater than 0");
+    Self {
+      frame_interval: Duration::from_secs(1) / fps,
+    }
+  }
+
+  /// Time slot for each frame.
+  pub fn frame_interval(&self) -> Duration {
+    self.frame_interval
+  }
+
+  /// Push every frame to the IC, sleeping between frames to hold the frame
+  /// rate.
+  ///
+  /// Frames are scheduled relative to the start of the run, so a slow write
+  /// doesn't delay every subsequent frame; instead, frames whose time slot
+  /// already passed are dropped. Returns once all frames are consumed.
+  pub fn run<D, I>(
+    &self,
+    ic: &mut LP55231<D>,
+    frames: I,
+  ) -> Result<AnimationReport, LinuxI2CError>
+  where
+    D: I2CDevice<Error = LinuxI2CError>,
+    I: IntoIterator<Item = [u8; 9]>,
+  {
+    let mut report = AnimationReport::default();
+    let start = Instant::now();
+    for (index, frame) in frames.into_iter().enumerate() {
+      let frame_end = start + self.frame_interval * (index as u32 + 1);
+      if Instant::now() >= frame_end {
+        report.frames_dropped += 1;
+        continue;
+      }
+
+      ic.set_all_pwm(frame)?;
+      report.frames_pushed += 1;
+
+      let now = Instant::now();
+      if now < frame_end {
+        sleep(frame_end - now);
+      }
+    }
+
+    Ok(report)
+  }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use linux_embedded_hal::i2cdev::linux::{LinuxI2CDevice, LinuxI2CError};
+
+use crate::{CurrentLimit, LP55231};
+
+/// Builder for configuring an [`LP55231`] driver before creating it.
+///
+/// Example:
+/// ```no_run
+/// # use ti_lp55231::LP55231;
+/// let ic = LP55231::builder("/dev/i2c-2", 0x32)
+///   .verify_writes(true)
+///   .disable_on_drop(true)
+///   .build()?;
+/// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct LP55231Builder {
+  path: String,
+  i2c_addr: u16,
+  debug_enabled: bool,
+  verify_writes: bool,
+  disable_on_drop: bool,
+  max_retries: u8,
+  retry_backoff: Duration,
+  current_limit: Option<CurrentLimit>,
+}
+
+impl LP55231Builder {
+  pub(crate) fn new(path: &str, i2c_addr: u16) -> Self {
+    Self {
+      path: path.to_string(),
+      i2c_addr,
+      debug_enabled: false,
+      verify_writes: false,
+      disable_on_drop: false,
+      max_retries: 0,
+      retry_backoff: Duration::from_millis(1),
+      current_limit: None,
+    }
+  }
+
+  /// See [`LP55231::debug_enabled`].
+  pub fn debug(mut self, enabled: bool) -> Self {
+    self.debug_enabled = enabled;
+    self
+  }
+
+  /// See [`LP55231::verify_writes`].
+  pub fn verify_writes(mut self, enabled: bool) -> Self {
+    self.verify_writes = enabled;
+    self
+  }
+
+  /// See [`LP55231::disable_on_drop`].
+  pub fn disable_on_drop(mut self, enabled: bool) -> Self {
+    self.disable_on_drop = enabled;
+    self
+  }
+
+  /// See [`LP55231::max_retries`] and [`LP55231::retry_backoff`].
+  pub fn retries(mut self, max_retries: u8, backoff: Duration) -> Self {
+    self.max_retries = max_retries;
+    self.retry_backoff = backoff;
+    self
+  }
+
+  /// See [`LP55231::current_limit`].
+  pub fn current_limit(mut self, limit: CurrentLimit) -> Self {
+    self.current_limit = Some(limit);
+    self
+  }
+
+  /// Create the driver with the configured options.
+  pub fn build(self) -> Result<LP55231<LinuxI2CDevice>, LinuxI2CError> {
+    let mut ic = LP55231::create(&self.path, self.i2c_addr)?;
+    ic.debug_enabled = self.debug_enabled;
+    ic.verify_writes = self.verify_writes;
+    ic.disable_on_drop = self.disable_on_drop;
+    ic.max_retries = self.max_retries;
+    ic.retry_backoff = self.retry_backoff;
+    ic.current_limit = self.current_limit;
+    Ok(ic)
+  }
+}
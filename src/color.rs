@@ -0,0 +1,50 @@
+//! Color conversions to per-channel PWM values.
+//!
+//! Values are returned in R, G, B order, to be applied to an
+//! [`RgbGroup`](crate::RgbGroup); example:
+//! ```no_run
+//! # use ti_lp55231::{color, Channel, RgbGroup, LP55231};
+//! # let mut ic = LP55231::create("/dev/i2c-2", 0x32)?;
+//! # let group = RgbGroup { r: Channel::D7, g: Channel::D1, b: Channel::D2 };
+//! let [r, g, b] = color::hsv_to_pwm(30.0, 1.0, 1.0);
+//! ic.set_rgb(&group, r, g, b)?;
+//! # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+//! ```
+
+use crate::gamma;
+
+/// Convert an RGB color to PWM values, optionally applying
+/// [gamma correction](gamma::correct) to each component.
+pub fn rgb_to_pwm(r: u8, g: u8, b: u8, gamma: Option<f32>) -> [u8; 3] {
+  match gamma {
+    Some(gamma) => [r, g, b].map(|value| gamma::correct(value, gamma)),
+    None => [r, g, b],
+  }
+}
+
+/// Convert an HSV color to PWM values.
+///
+/// `hue` is in degrees and wraps around (e.g. 360 is red, like 0).
+/// `saturation` and `value` range from 0 to 1 and are clamped to that range.
+///
+/// No gamma correction is applied; see [`rgb_to_pwm`].
+pub fn hsv_to_pwm(hue: f32, saturation: f32, value: f32) -> [u8; 3] {
+  let hue = hue.rem_euclid(360.0);
+  let saturation = saturation.clamp(0.0, 1.0);
+  let value = value.clamp(0.0, 1.0);
+
+  let chroma = value * saturation;
+  let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+  let m = value - chroma;
+
+  let (r, g, b) = match hue {
+    h if h < 60.0 => (chroma, x, 0.0),
+    h if h < 120.0 => (x, chroma, 0.0),
+    h if h < 180.0 => (0.0, chroma, x),
+    h if h < 240.0 => (0.0, x, chroma),
+    h if h < 300.0 => (x, 0.0, chroma),
+    _ => (chroma, 0.0, x),
+  };
+
+  [r, g, b].map(|component| ((component + m) * u8::MAX as f32).round() as u8)
+}
@@ -0,0 +1,58 @@
+/// Output current per step of a channel current value, in milliamps.
+pub const CURRENT_STEP_MA: f32 = 0.1;
+/// Maximum output current of a channel, in milliamps.
+pub const MAX_CURRENT_MA: f32 = 25.5;
+
+/// Convert a current in milliamps to a channel current value (see
+/// [`LP55231::set_channel_current`](crate::LP55231::set_channel_current)),
+/// rounding to the nearest 0.1mA step.
+///
+/// Returns `None` if `ma` is outside the range \[0:25.5\].
+pub fn current_from_ma(ma: f32) -> Option<u8> {
+  if !(0.0..=MAX_CURRENT_MA).contains(&ma) {
+    return None;
+  }
+
+  Some((ma / CURRENT_STEP_MA).round() as u8)
+}
+
+/// Convert a channel current value to milliamps.
+pub fn current_to_ma(raw: u8) -> f32 {
+  raw as f32 * CURRENT_STEP_MA
+}
+
+/// Channel current value for the full-scale output current (25.5mA).
+pub const CURRENT_FULL_SCALE: u8 = u8::MAX;
+
+/// Upper bound for channel current values, to protect LEDs rated for less than
+/// the [full-scale](CURRENT_FULL_SCALE) output current.
+///
+/// See [`LP55231::current_limit`](crate::LP55231::current_limit). Example:
+/// ```no_run
+/// # use ti_lp55231::{current_from_ma, CurrentLimit, LP55231};
+/// # let mut ic = LP55231::create("/dev/i2c-2", 0x32)?;
+/// // LEDs rated for 10mA.
+/// ic.current_limit = Some(CurrentLimit::Reject(current_from_ma(10.0).unwrap()));
+/// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CurrentLimit {
+  /// Lower values above the limit to the limit.
+  Clamp(u8),
+  /// Fail when setting a value above the limit.
+  Reject(u8),
+}
+
+impl CurrentLimit {
+  /// Maximum channel current value allowed.
+  pub fn max(&self) -> u8 {
+    match self {
+      CurrentLimit::Clamp(max) | CurrentLimit::Reject(max) => *max,
+    }
+  }
+
+  /// Maximum output current allowed, in milliamps.
+  pub fn max_ma(&self) -> f32 {
+    current_to_ma(self.max())
+  }
+}
@@ -1,4 +1,14 @@
-/// Macros for low level debugging when developing against I2C devices.
+//! Macros for low level debugging when developing against I2C devices.
+//!
+//! Output is printed to stdout, unless redirected to a [`DebugSink`]. With
+//! the `log` feature, output not redirected to a sink is instead logged at
+//! trace level via `log::trace!`, keeping its indentation. In both cases,
+//! output is only produced when the context's `debug_enabled` is set.
+
+use std::{
+  io::Write,
+  sync::{Arc, Mutex},
+};
 
 /// Create a debugging scope on the provided context.
 ///
@@ -8,13 +18,17 @@
 ///
 /// Scopes can be nested, where the inner scope will further pad any output.
 ///
-/// The provided context (`ctx`) can be any struct that has two fields
+/// The provided context (`ctx`) can be any struct that has three fields
 /// accessible to these macros:
 /// 1. `debug_enabled: bool`
-/// 2. `debug_depth: Rc<Cell<usize>>`
+/// 2. `debug_depth: Arc<Mutex<usize>>`
+/// 3. `debug_sink: DebugSink`
+///
+/// `debug_depth` is only locked when `debug_enabled` is set; otherwise the
+/// macro expands to a branch producing `None`.
 ///
 /// Example:
-/// ```
+/// ```ignore
 /// debug::scope!(ctx, "entering scope 1");
 /// debug::text!(ctx, "message 1")
 /// debug::scope!(ctx, "entering scope 2");
@@ -38,7 +52,7 @@
 ///
 /// The nesting ability of scopes becomes really useful when composing I2C
 /// operations; example:
-/// ```
+/// ```ignore
 /// fn start() {
 ///   debug::scope!("start()");
 ///   let byte = 1;
@@ -81,20 +95,24 @@ macro_rules! scope {
     // decrements debug depth.
     let _unused = if $ctx.debug_enabled {
       let mut cur_depth = $ctx.debug_depth.lock().unwrap();
-      let padding = "  ".repeat(*cur_depth);
-      println!("{}{} {{", padding, format!($fmt $(, $arg)*));
+      $crate::debug::emit(
+        &$ctx.debug_sink,
+        *cur_depth,
+        &format!("{} {{", format!($fmt $(, $arg)*)),
+      );
       *cur_depth += 1;
       // lock releases after return
 
       // debug_depth ArcMutex needs to be cloned since guard() takes ownership
       // of the arg. Code in block below is executed at the end of the code
       // scope where the `scope` macro is called.
-      Some(scopeguard::guard($ctx.debug_depth.clone(), |depth| {
+      let sink = $ctx.debug_sink.clone();
+      Some(scopeguard::guard($ctx.debug_depth.clone(), move |depth| {
         let mut cur_depth = depth.lock().unwrap();
         if *cur_depth > 0 {
           *cur_depth -= 1;
         }
-        println!("{}}}", "  ".repeat(*cur_depth));
+        $crate::debug::emit(&sink, *cur_depth, "}");
       }))
     } else {
       None
@@ -106,7 +124,7 @@ pub use scope;
 /// Print a formated message at current debug depth.
 ///
 /// Example:
-/// ```
+/// ```ignore
 /// debug::text!(ctx, "1. top-level debug text");
 /// debug::scope!(ctx, "2. in-scope");
 /// debug::text!(ctx, "2a. in-scope debug text");
@@ -122,8 +140,8 @@ pub use scope;
 macro_rules! text {
   ($ctx:expr, $fmt:expr $(, $arg:expr)* $(,)?) => {
     if $ctx.debug_enabled {
-      let padding = "  ".repeat(*$ctx.debug_depth.lock().unwrap());
-      println!("{}{}", padding, format!($fmt $(, $arg)*));
+      let depth = *$ctx.debug_depth.lock().unwrap();
+      $crate::debug::emit(&$ctx.debug_sink, depth, &format!($fmt $(, $arg)*));
     };
   };
 }
@@ -133,7 +151,7 @@ pub use text;
 /// description at the current debug depth.
 ///
 /// Example:
-/// ```
+/// ```ignore
 /// let value = 0b0010_1010;
 /// debug::byte(value, "is binary for {}", value);
 /// ```
@@ -157,3 +175,61 @@ macro_rules! byte {
   };
 }
 pub use byte;
+
+/// Destination for debug output; stdout (or the `log` crate, with the `log`
+/// feature) when `None`.
+pub type DebugSink = Arc<Mutex<Option<Box<dyn Write + Send>>>>;
+
+/// Print a line of debug output to `sink`, indented by `depth`.
+///
+/// All output of the macros in this module goes through this function.
+/// Errors writing to the sink are ignored.
+#[doc(hidden)]
+pub fn emit(sink: &DebugSink, depth: usize, line: &str) {
+  let padding = "  ".repeat(depth);
+  match sink.lock().unwrap().as_mut() {
+    Some(writer) => {
+      let _ = writeln!(writer, "{}{}", padding, line);
+    }
+    #[cfg(feature = "log")]
+    None => log::trace!("{}{}", padding, line),
+    #[cfg(not(feature = "log"))]
+    None => println!("{}{}", padding, line),
+  }
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+  use super::*;
+
+  static LINES: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+  struct Logger;
+
+  impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+      metadata.level() == log::Level::Trace
+    }
+
+    fn log(&self, record: &log::Record) {
+      LINES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+  }
+
+  #[test]
+  fn emit_logs_indented_lines_unless_redirected() {
+    log::set_logger(&Logger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let sink: DebugSink = Arc::new(Mutex::new(None));
+    emit(&sink, 0, "scope {");
+    emit(&sink, 1, "00000001 write");
+    assert_eq!(*LINES.lock().unwrap(), ["scope {", "  00000001 write"]);
+
+    *sink.lock().unwrap() = Some(Box::new(std::io::sink()));
+    emit(&sink, 0, "redirected");
+    assert_eq!(LINES.lock().unwrap().len(), 2);
+  }
+}
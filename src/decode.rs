@@ -0,0 +1,275 @@
+use crate::{Channel, Direction, Instruction, PreScale, Variable};
+
+/// Decoded representation of a programming engine [`Instruction`].
+///
+/// Mirrors the [`Instruction`] builders; see each builder for details on the
+/// fields.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DecodedInstruction {
+  // Driver instructions
+  Ramp {
+    cycle_time: PreScale,
+    cycles_per_step: u8,
+    direction: Direction,
+    number_of_steps: u8,
+  },
+  RampFromVars {
+    cycle_time: PreScale,
+    direction: Direction,
+    step_time_var: Variable,
+    increments_var: Variable,
+  },
+  SetPwm(u8),
+  SetPwmFromVar(Variable),
+  Wait {
+    cycle_time: PreScale,
+    cycles: u8,
+  },
+
+  // Mapping instructions
+  MuxLdStart(u8),
+  MuxMapStart(u8),
+  MuxLdEnd(u8),
+  MuxSel(u8),
+  MuxClr,
+  MuxMapNext,
+  MuxMapPrev,
+  MuxLdNext,
+  MuxLdPrev,
+  MuxLdAddr(u8),
+  MuxMapAddr(u8),
+
+  // Branch instructions
+  Rst,
+  Branch {
+    step_number: u8,
+    loop_count: u8,
+  },
+  BranchVars {
+    step_number: u8,
+    loop_count_var: Variable,
+  },
+  Int,
+  End {
+    interrupt: bool,
+    reset_program_counter: bool,
+  },
+  Jne {
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  },
+  Jl {
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  },
+  Jge {
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  },
+  Je {
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  },
+
+  // Arithmetic instructions
+  Ld {
+    target_var: Variable,
+    value: u8,
+  },
+  AddNumerical {
+    target_var: Variable,
+    value: u8,
+  },
+  AddVars {
+    target_var: Variable,
+    var_1: Variable,
+    var_2: Variable,
+  },
+  SubNumerical {
+    target_var: Variable,
+    value: u8,
+  },
+  SubVars {
+    target_var: Variable,
+    var_1: Variable,
+    var_2: Variable,
+  },
+
+  /// Word not produced by any of the [`Instruction`] builders.
+  Unknown(u16),
+}
+
+impl Instruction {
+  /// Decode the instruction.
+  ///
+  /// NB: LED mapping table entries (see [`Instruction::map_channels`]) are
+  /// data rather than instructions, and decode as whichever instruction
+  /// shares their bit pattern; use [`Self::mapped_channels`] for those.
+  pub fn decode(&self) -> DecodedInstruction {
+    use DecodedInstruction::*;
+
+    let word = self.as_u16();
+    let (msb, lsb) = (self.msb, self.lsb);
+
+    match msb {
+      // 0b0xxx_xxxx: ramp, wait, set_pwm and rst.
+      0b0000_0000..=0b0111_1111 => {
+        let cycle_time = pre_scale((msb >> 6) & 0b1);
+        let cycles = (msb >> 1) & 0b1_1111;
+        if word == 0 {
+          Rst
+        } else if msb == 0b0100_0000 {
+          SetPwm(lsb)
+        } else if lsb == 0 && msb & 0b1 == 0 {
+          Wait { cycle_time, cycles }
+        } else {
+          Ramp {
+            cycle_time,
+            cycles_per_step: cycles,
+            direction: direction(msb & 0b1),
+            number_of_steps: lsb,
+          }
+        }
+      }
+      0b1000_0100 if lsb & 0b1111_1100 == 0b0110_0000 => {
+        SetPwmFromVar(variable(lsb))
+      }
+      0b1000_0100 if lsb & 0b1000_0000 == 0 => RampFromVars {
+        cycle_time: pre_scale((lsb >> 6) & 0b1),
+        direction: direction((lsb >> 5) & 0b1),
+        step_time_var: variable(lsb >> 2),
+        increments_var: variable(lsb),
+      },
+      0b1000_0110 | 0b1000_0111 => BranchVars {
+        step_number: ((word >> 2) & 0b111_1111) as u8,
+        loop_count_var: variable(lsb),
+      },
+      0b1000_1000..=0b1000_1111 => {
+        let num_instructions_to_skip = ((word >> 4) & 0b1_1111) as u8;
+        let (low_var, high_var) = (variable(lsb), variable(lsb >> 2));
+        match msb & 0b1111_1110 {
+          0b1000_1000 => Jne {
+            num_instructions_to_skip,
+            var_1: low_var,
+            var_2: high_var,
+          },
+          0b1000_1010 => Jl {
+            num_instructions_to_skip,
+            var_1: high_var,
+            var_2: low_var,
+          },
+          0b1000_1100 => Jge {
+            num_instructions_to_skip,
+            var_1: high_var,
+            var_2: low_var,
+          },
+          _ => Je {
+            num_instructions_to_skip,
+            var_1: high_var,
+            var_2: low_var,
+          },
+        }
+      }
+      0b1001_0000..=0b1001_1011 => {
+        let target_var = variable(msb >> 2);
+        let (var_1, var_2) = (variable(lsb >> 2), variable(lsb));
+        match msb & 0b11 {
+          0b00 => Ld {
+            target_var,
+            value: lsb,
+          },
+          0b01 => AddNumerical {
+            target_var,
+            value: lsb,
+          },
+          0b10 => SubNumerical {
+            target_var,
+            value: lsb,
+          },
+          _ if lsb & 0b1110_0000 != 0 => Unknown(word),
+          _ if lsb & 0b0001_0000 == 0 => AddVars {
+            target_var,
+            var_1,
+            var_2,
+          },
+          _ => SubVars {
+            target_var,
+            var_1,
+            var_2,
+          },
+        }
+      }
+      0b1001_1100 if lsb & 0b1000_0000 == 0 => MuxMapStart(lsb),
+      0b1001_1100 => MuxLdEnd(lsb & 0b0111_1111),
+      0b1001_1101 => match lsb {
+        0b0000_0000 => MuxClr,
+        0b1000_0000 => MuxMapNext,
+        0b1100_0000 => MuxMapPrev,
+        0b1000_0001 => MuxLdNext,
+        0b1100_0001 => MuxLdPrev,
+        _ => MuxSel(lsb),
+      },
+      0b1001_1110 => MuxLdStart(lsb),
+      0b1001_1111 if lsb & 0b1000_0000 == 0 => MuxLdAddr(lsb),
+      0b1001_1111 => MuxMapAddr(lsb & 0b0111_1111),
+      0b1010_0000..=0b1011_1111 => Branch {
+        step_number: (word & 0b111_1111) as u8,
+        loop_count: ((word >> 7) & 0b11_1111) as u8,
+      },
+      0b1100_0100 if lsb == 0 => Int,
+      _ if msb & 0b1110_0111 == 0b1100_0000 && lsb == 0 => End {
+        interrupt: msb & (1 << 4) != 0,
+        reset_program_counter: msb & (1 << 3) != 0,
+      },
+      _ => Unknown(word),
+    }
+  }
+
+  /// Decode the instruction as an LED mapping table entry, inverting
+  /// [`Instruction::map_channels`]: returns the mapped channels, ordered D1
+  /// through D9.
+  ///
+  /// Returns `None` if any of bits 9 through 15 are set, as the word can't be
+  /// a mapping table entry. An entry mapping no channels returns an empty
+  /// vector.
+  pub fn mapped_channels(&self) -> Option<Vec<Channel>> {
+    let word = self.as_u16();
+    if word & 0b1111_1110_0000_0000 != 0 {
+      return None;
+    }
+
+    let channels = Channel::all()
+      .into_iter()
+      .filter(|channel| word & (1 << *channel as u8) != 0)
+      .collect();
+    Some(channels)
+  }
+}
+
+fn pre_scale(bit: u8) -> PreScale {
+  match bit {
+    0 => PreScale::CT0_488,
+    _ => PreScale::CT15_625,
+  }
+}
+
+fn direction(bit: u8) -> Direction {
+  match bit {
+    0 => Direction::Up,
+    _ => Direction::Down,
+  }
+}
+
+// Variable encoded in the two least significant bits.
+fn variable(bits: u8) -> Variable {
+  match bits & 0b11 {
+    0b00 => Variable::A,
+    0b01 => Variable::B,
+    0b10 => Variable::C,
+    _ => Variable::D,
+  }
+}
@@ -0,0 +1,201 @@
+use linux_embedded_hal::i2cdev::{
+  core::I2CDevice,
+  linux::{LinuxI2CDevice, LinuxI2CError},
+};
+
+use crate::{debug, Channel, Fader, LP55231};
+
+/// An arbitrary bank of output channels (e.g. a 5-LED strip on D1 through D5)
+/// controlled as one.
+///
+/// Each method applies the equivalent per-channel method of [`LP55231`] to
+/// every member, in order.
+///
+/// Example:
+/// ```no_run
+/// # use ti_lp55231::{Channel, ChannelGroup, LP55231};
+/// # let mut ic = LP55231::create("/dev/i2c-2", 0x32)?;
+/// let strip = ChannelGroup::new(&[Channel::D1, Channel::D2, Channel::D3]);
+/// strip.enable(&mut ic, true)?;
+/// strip.set_pwm(&mut ic, 128)?;
+/// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChannelGroup {
+  pub channels: Vec<Channel>,
+}
+
+impl ChannelGroup {
+  /// Create a group of the specified channels.
+  pub fn new(channels: &[Channel]) -> Self {
+    Self {
+      channels: channels.to_vec(),
+    }
+  }
+
+  /// Set the same PWM value for every channel in the group (see
+  /// [`LP55231::set_channel_pwm`]).
+  pub fn set_pwm<D>(
+    &self,
+    ic: &mut LP55231<D>,
+    pwm: u8,
+  ) -> Result<(), LinuxI2CError>
+  where
+    D: I2CDevice<Error = LinuxI2CError>,
+  {
+    debug::scope!(
+      ic,
+      "ChannelGroup::set_pwm({:?}, pwm: {})",
+      self.channels,
+      pwm
+    );
+
+    for channel in &self.channels {
+      ic.set_channel_pwm(*channel, pwm)?;
+    }
+
+    Ok(())
+  }
+
+  /// Set the same current value for every channel in the group (see
+  /// [`LP55231::set_channel_current`]).
+  pub fn set_current<D>(
+    &self,
+    ic: &mut LP55231<D>,
+    current: u8,
+  ) -> Result<(), LinuxI2CError>
+  where
+    D: I2CDevice<Error = LinuxI2CError>,
+  {
+    debug::scope!(
+      ic,
+      "ChannelGroup::set_current({:?}, current: {})",
+      self.channels,
+      current
+    );
+
+    for channel in &self.channels {
+      ic.set_channel_current(*channel, current)?;
+    }
+
+    Ok(())
+  }
+
+  /// Enable or disable every channel in the group (see
+  /// [`LP55231::set_channel_enabled`]).
+  pub fn enable<D>(
+    &self,
+    ic: &mut LP55231<D>,
+    enabled: bool,
+  ) -> Result<(), LinuxI2CError>
+  where
+    D: I2CDevice<Error = LinuxI2CError>,
+  {
+    debug::scope!(
+      ic,
+      "ChannelGroup::enable({:?}, enabled: {})",
+      self.channels,
+      enabled
+    );
+
+    for channel in &self.channels {
+      ic.set_channel_enabled(*channel, enabled)?;
+    }
+
+    Ok(())
+  }
+
+  /// Assign every channel in the group to the specified [`Fader`], or remove
+  /// their fader associations if `None` (see [`LP55231::assign_to_fader`]).
+  pub fn assign_fader<D>(
+    &self,
+    ic: &mut LP55231<D>,
+    fader: Option<Fader>,
+  ) -> Result<(), LinuxI2CError>
+  where
+    D: I2CDevice<Error = LinuxI2CError>,
+  {
+    debug::scope!(
+      ic,
+      "ChannelGroup::assign_fader({:?}, fader: {:?})",
+      self.channels,
+      fader
+    );
+
+    for channel in &self.channels {
+      ic.assign_to_fader(*channel, fader)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl From<Vec<Channel>> for ChannelGroup {
+  fn from(channels: Vec<Channel>) -> Self {
+    Self { channels }
+  }
+}
+
+/// Several LP55231 chips (e.g. at different addresses on the same bus)
+/// controlled as one bank of channels.
+///
+/// Channels are numbered across devices in order: the first device's D1
+/// through D9, then the second device's D1 through D9, and so on.
+///
+/// Example:
+/// ```no_run
+/// # use ti_lp55231::{DeviceGroup, LP55231};
+/// let mut chips = DeviceGroup::new(vec![
+///   LP55231::create("/dev/i2c-2", 0x32)?,
+///   LP55231::create("/dev/i2c-2", 0x33)?,
+/// ]);
+/// chips.set_all_pwm(&[255; 18])?;
+/// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+/// ```
+pub struct DeviceGroup<D = LinuxI2CDevice>
+where
+  D: I2CDevice<Error = LinuxI2CError>,
+{
+  pub devices: Vec<LP55231<D>>,
+}
+
+impl<D> DeviceGroup<D>
+where
+  D: I2CDevice<Error = LinuxI2CError>,
+{
+  /// Create a group of the specified devices, in channel order.
+  pub fn new(devices: Vec<LP55231<D>>) -> Self {
+    Self { devices }
+  }
+
+  /// Total number of channels across all devices.
+  pub fn channel_count(&self) -> usize {
+    self.devices.len() * Channel::all().len()
+  }
+
+  /// Set the PWM values for all channels of all devices, in channel order.
+  ///
+  /// Each device gets its 9 values in a single call to
+  /// [`LP55231::set_all_pwm`] (i.e. one I2C write per device when
+  /// `EN_AUTO_INCR` is set), so devices are updated back to back.
+  ///
+  /// `values` must hold exactly [`Self::channel_count`] values.
+  pub fn set_all_pwm(&mut self, values: &[u8]) -> Result<(), LinuxI2CError> {
+    if values.len() != self.channel_count() {
+      return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+        "invalid number of values ({}); expected {}",
+        values.len(),
+        self.channel_count()
+      ))));
+    }
+
+    let frames = values.chunks_exact(Channel::all().len());
+    for (device, frame) in self.devices.iter_mut().zip(frames) {
+      let mut pwm = [0; 9];
+      pwm.copy_from_slice(frame);
+      device.set_all_pwm(pwm)?;
+    }
+
+    Ok(())
+  }
+}
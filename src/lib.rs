@@ -1,53 +1,205 @@
 use std::{
+  io::Write,
   sync::{Arc, Mutex},
   thread::sleep,
-  time::Duration,
+  time::{Duration, Instant},
 };
 
+use embedded_hal::digital::v2::InputPin;
 use linux_embedded_hal::i2cdev::{
   core::I2CDevice,
   linux::{LinuxI2CDevice, LinuxI2CError},
 };
 
+mod animator;
+mod builder;
+pub mod color;
+mod current;
 pub mod debug;
+mod decode;
+pub mod gamma;
+mod group;
 mod mask;
-mod program;
+pub mod program;
 mod register;
+mod simulator;
+#[cfg(feature = "mock")]
+pub mod test_util;
+pub mod timing;
 mod types;
 
+pub use animator::*;
+pub use builder::*;
+pub use current::*;
+pub use decode::*;
+pub use group::*;
 pub use mask::*;
 pub use program::*;
 pub use register::*;
+pub use simulator::*;
 pub use types::*;
 
 /// Driver for Texas Instruments LP55231 I²C via [embedded-hal].
 ///
 /// For more details, please refer to the [technical specs].
 ///
+/// Generic over the underlying I2C device, which defaults to
+/// [`LinuxI2CDevice`]; any other [`I2CDevice`] sharing its error type can be
+/// used via [`Self::new`] (e.g. the in-memory `MockI2c` from `test_util`,
+/// available with the `mock` feature).
+///
 /// [embedded-hal]: https://docs.rs/embedded-hal
 /// [technical specs]: (https://www.ti.com/lit/ds/symlink/lp55231.pdf).
-pub struct LP55231 {
-  device: LinuxI2CDevice,
+pub struct LP55231<D = LinuxI2CDevice>
+where
+  D: I2CDevice<Error = LinuxI2CError>,
+{
+  device: D,
   /// Enable debug output.
   ///
-  /// Will print address and values for every I2C read and write instruction.
+  /// Will print (or log, with the `log` feature) address and values for every
+  /// I2C read and write instruction.
+  /// When disabled, the [debug macros](debug) neither format output nor lock
+  /// `debug_depth`, so they add no overhead beyond a branch.
   pub debug_enabled: bool,
   /// Read-after-write verification
+  ///
+  /// Skipped for registers that can't be read back as written (see
+  /// [`Register::is_verifiable`]).
   pub verify_writes: bool,
+  /// Turn outputs off and disable the IC when dropped.
+  ///
+  /// See [`Self::stop`]. Errors are ignored since they can't be propagated
+  /// from [`Drop`].
+  pub disable_on_drop: bool,
+  /// Number of times to retry an I2C transfer failing with a transient error
+  /// (e.g. a NAK on a noisy bus) before giving up.
+  ///
+  /// Disabled (0) by default.
+  pub max_retries: u8,
+  /// Delay before the first retry; each further retry waits an additional
+  /// `retry_backoff` (i.e. linear backoff).
+  pub retry_backoff: Duration,
+  /// Upper bound applied to channel current values by
+  /// [`Self::set_channel_current`] and [`Self::set_all_current`].
+  ///
+  /// No limit (`None`) by default.
+  pub current_limit: Option<CurrentLimit>,
+  // Last known state of `EN_AUTO_INCR`, tracked on every MISC read and write.
+  auto_increment: Option<bool>,
+  // Last known program memory page, tracked on every PROG MEM PAGE SEL read
+  // and write.
+  page: Option<u8>,
   #[doc(hidden)]
   pub debug_depth: Arc<Mutex<usize>>,
+  #[doc(hidden)]
+  pub debug_sink: debug::DebugSink,
 }
 
-impl LP55231 {
+impl LP55231<LinuxI2CDevice> {
   /// Create a new LP55231 abstraction for the specified path and I2C address.
   pub fn create(path: &str, i2c_addr: u16) -> Result<Self, LinuxI2CError> {
     let device = LinuxI2CDevice::new(path, i2c_addr)?;
-    Ok(Self {
+    Ok(Self::new(device))
+  }
+
+  /// Configure a new LP55231 abstraction for the specified path and I2C
+  /// address; see [`LP55231Builder`].
+  pub fn builder(path: &str, i2c_addr: u16) -> LP55231Builder {
+    LP55231Builder::new(path, i2c_addr)
+  }
+
+  /// Scan the I2C bus at `path` for devices responding at any of the
+  /// specified addresses.
+  ///
+  /// Returns the addresses where a register read succeeded. Reads have no
+  /// side effects, but note that any device answering is reported.
+  pub fn scan(path: &str, addrs: &[u16]) -> Vec<u16> {
+    addrs
+      .iter()
+      .copied()
+      .filter(|addr| {
+        LinuxI2CDevice::new(path, *addr)
+          .and_then(|mut device| {
+            device.smbus_read_byte_data(Register::ENABLE_ENGINE_CNTRL1 as u8)
+          })
+          .is_ok()
+      })
+      .collect()
+  }
+}
+
+impl<D> LP55231<D>
+where
+  D: I2CDevice<Error = LinuxI2CError>,
+{
+  /// Create a new LP55231 abstraction over the specified I2C device.
+  pub fn new(device: D) -> Self {
+    Self {
       device,
       debug_enabled: false,
       verify_writes: false,
+      disable_on_drop: false,
+      max_retries: 0,
+      retry_backoff: Duration::from_millis(1),
+      current_limit: None,
+      auto_increment: None,
+      page: None,
       debug_depth: Arc::new(Mutex::new(0)),
-    })
+      debug_sink: Arc::new(Mutex::new(None)),
+    }
+  }
+
+  /// Write [debug output](Self::debug_enabled) to `sink` instead of stdout,
+  /// e.g. to capture a trace of I2C transactions into a buffer.
+  pub fn set_debug_sink(&mut self, sink: impl Write + Send + 'static) {
+    *self.debug_sink.lock().unwrap() = Some(Box::new(sink));
+  }
+
+  /// The underlying I2C device.
+  pub fn device(&self) -> &D {
+    &self.device
+  }
+
+  /// The underlying I2C device, mutably.
+  ///
+  /// Bypassing the driver leaves [debug output](Self::debug_enabled) and
+  /// [write verification](Self::verify_writes) out of the loop, and causes
+  /// the auto-increment state to be read again on next use (and the program
+  /// memory page to be selected again).
+  pub fn device_mut(&mut self) -> &mut D {
+    self.auto_increment = None;
+    self.page = None;
+    &mut self.device
+  }
+
+  /// Check whether the device at the configured address plausibly is an
+  /// LP55231.
+  ///
+  /// Performs side-effect free reads of registers with unused bits that are
+  /// always read as zero on an LP55231, regardless of its state:
+  /// - `PROG MEM PAGE SEL` must hold a valid page number
+  /// - `ENGINE1 PC` through `ENGINE3 PC` must fit 7 bits
+  ///
+  /// A device failing these checks is definitely not an LP55231; one passing
+  /// them most likely is. Bus errors (e.g. no device at the address) are
+  /// returned as errors.
+  pub fn probe(&mut self) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "probe()");
+
+    let page = self.read_register(Register::PROG_MEM_PAGE_SEL)?;
+    if page >= MAX_PAGES {
+      return Ok(false);
+    }
+
+    for engine in Engine::all() {
+      let pc = self.read_register(Register::program_counter_for(engine))?;
+      if pc > 0b0111_1111 {
+        return Ok(false);
+      }
+    }
+
+    Ok(true)
   }
 
   /// Reset the IC.
@@ -60,6 +212,43 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Reset the IC and wait for it to finish initializing.
+  ///
+  /// See [`Self::wait_while_startup_busy`].
+  pub fn reset_and_wait(
+    &mut self,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "reset_and_wait()");
+
+    self.reset()?;
+    self.wait_while_startup_busy(poll_interval, timeout)
+  }
+
+  /// Reset, configure, and enable the IC.
+  ///
+  /// Performs the whole bring up sequence:
+  /// 1. [Resets](Self::reset) the IC and waits for startup to complete
+  /// 2. Applies the specified [misc](Misc) settings
+  /// 3. Enables the IC and waits for the internal oscillator to start up
+  ///
+  /// The IC is ready to use once this method returns.
+  pub fn initialize(&mut self, misc: Misc) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "initialize({:?})", misc);
+
+    self
+      .reset_and_wait(timing::STARTUP_POLL_INTERVAL, timing::STARTUP_TIMEOUT)?;
+    self.set_misc_settings(misc)?;
+    self.set_enabled(true)?;
+
+    // The spec requires a 500µs delay after setting CHIP_EN, for the internal
+    // oscillator to start up, before issuing any other command.
+    sleep(timing::CHIP_EN_STARTUP);
+
+    Ok(())
+  }
+
   /// Test whether the IC is currently enabled.
   pub fn is_enabled(&mut self) -> Result<bool, LinuxI2CError> {
     debug::scope!(self, "is_enabled()");
@@ -93,6 +282,17 @@ impl LP55231 {
     Ok(misc)
   }
 
+  /// Read the raw value of the `MISC` register.
+  ///
+  /// Unlike [`Self::get_misc_settings`], bits not modeled by [`Misc`] are
+  /// preserved, which helps spotting discrepancies between the decoded
+  /// settings and the register.
+  pub fn get_misc_raw(&mut self) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_misc_raw()");
+
+    self.read_register(Register::MISC)
+  }
+
   /// Set [misc](Misc) settings for the IC.
   ///
   /// Overrides all existing settings.
@@ -110,7 +310,233 @@ impl LP55231 {
     let value =
       en_auto_incr | powersave_en | cp_mode | pwm_ps_en | clk_det_en_int_clk_en;
 
-    self.write_register(Register::MISC, value)
+    // With write verification, report which settings didn't take, on top of
+    // the raw values.
+    let verify_writes = std::mem::replace(&mut self.verify_writes, false);
+    let result = self.write_register(Register::MISC, value);
+    self.verify_writes = verify_writes;
+    result?;
+
+    if self.verify_writes {
+      let post_write_value = self.get_misc_raw()?;
+      if post_write_value != value {
+        let fields = misc.diff(&self.get_misc_settings()?);
+        return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+          "write to register {} failed; read-after-write expecting {:08b} but got {:08b} (differing settings: {:?})",
+          Register::MISC,
+          value,
+          post_write_value,
+          fields
+        ))));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Enable or disable register address auto-increment (`EN_AUTO_INCR`).
+  ///
+  /// Unlike [`Self::set_misc_settings`], leaves other [misc](Misc) settings
+  /// untouched.
+  ///
+  /// Block transfers (e.g. [`Self::write_registers`],
+  /// [`Self::read_program_page`]) never issue a block transfer while
+  /// auto-increment is disabled, which would repeatedly access the same
+  /// register; they fail with [`AutoIncrementRequired`] instead (see
+  /// [`Self::is_auto_increment_enabled`]). Methods merely using block
+  /// transfers as an optimization (e.g. [`Self::set_all_pwm`],
+  /// [`Self::load_program`]) fall back to byte-by-byte transfers. Disabling
+  /// auto-increment from outside the driver invalidates its tracked state;
+  /// call [`Self::get_misc_settings`] to refresh it.
+  pub fn set_auto_increment(
+    &mut self,
+    enabled: bool,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_auto_increment({})", enabled);
+
+    self.update_misc(Mask::EN_AUTO_INCR, enabled as u8)
+  }
+
+  /// Test whether register address auto-increment (`EN_AUTO_INCR`) is
+  /// enabled.
+  ///
+  /// The state is tracked whenever `MISC` is read or written through the
+  /// driver, so the register is only read when the state is unknown (e.g.
+  /// after a [reset](Self::reset)). Block transfers check this before
+  /// accessing the device (see [`Self::set_auto_increment`]).
+  pub fn is_auto_increment_enabled(&mut self) -> Result<bool, LinuxI2CError> {
+    if let Some(enabled) = self.auto_increment {
+      return Ok(enabled);
+    }
+
+    let value = self.read_register(Register::MISC)?;
+    Ok(Mask::EN_AUTO_INCR.is_set(value))
+  }
+
+  /// Enable or disable powersave mode (`POWERSAVE_EN`).
+  ///
+  /// Unlike [`Self::set_misc_settings`], leaves other [misc](Misc) settings
+  /// untouched.
+  pub fn set_powersave(&mut self, enabled: bool) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_powersave({})", enabled);
+
+    self.update_misc(Mask::POWERSAVE_EN, enabled as u8)
+  }
+
+  /// Set the [`ChargePumpMode`] (`CP_MODE`).
+  ///
+  /// Unlike [`Self::set_misc_settings`], leaves other [misc](Misc) settings
+  /// untouched.
+  pub fn set_charge_pump_mode(
+    &mut self,
+    mode: ChargePumpMode,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_charge_pump_mode({:?})", mode);
+
+    self.update_misc(Mask::CP_MODE, mode as u8)
+  }
+
+  /// Read the [`ChargePumpMode`] (`CP_MODE`).
+  ///
+  /// Since `CP_MODE` is the only charge pump setting, this fully describes the
+  /// charge pump configuration.
+  pub fn get_charge_pump_mode(
+    &mut self,
+  ) -> Result<ChargePumpMode, LinuxI2CError> {
+    debug::scope!(self, "get_charge_pump_mode()");
+
+    let value = self.read_register(Register::MISC)?;
+    Ok(ChargePumpMode::from(Mask::CP_MODE.value(value)))
+  }
+
+  /// Enable or disable PWM cycle powersave (`PWM_PS_EN`).
+  ///
+  /// Unlike [`Self::set_misc_settings`], leaves other [misc](Misc) settings
+  /// untouched.
+  pub fn set_pwm_powersave(
+    &mut self,
+    enabled: bool,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_pwm_powersave({})", enabled);
+
+    self.update_misc(Mask::PWM_PS_EN, enabled as u8)
+  }
+
+  /// Set the [`ClockSelection`] (`CLK_DET_EN` and `INT_CLK_EN`).
+  ///
+  /// Unlike [`Self::set_misc_settings`], leaves other [misc](Misc) settings
+  /// untouched.
+  pub fn set_clock_selection(
+    &mut self,
+    clock_selection: ClockSelection,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_clock_selection({:?})", clock_selection);
+
+    self.update_misc(Mask::CLK_DET_EN, clock_selection as u8)
+  }
+
+  // Read-modify-write a single MISC field (if different).
+  fn update_misc(
+    &mut self,
+    mask: Mask,
+    value: u8,
+  ) -> Result<(), LinuxI2CError> {
+    let current_value = self.read_register(Register::MISC)?;
+    let new_value = mask.apply(value, current_value);
+    if new_value != current_value {
+      self.write_register(Register::MISC, new_value)?;
+    }
+
+    Ok(())
+  }
+
+  /// Read the current [INT/GPO](IntGpo) pin configuration from the IC.
+  ///
+  /// Inverse of [`Self::set_int_gpo`]; e.g. to toggle the GPO pin without
+  /// keeping track of its state:
+  /// ```no_run
+  /// # let mut ic = ti_lp55231::LP55231::create("/dev/i2c-2", 0x32)?;
+  /// let mut int_gpo = ic.get_int_gpo()?;
+  /// int_gpo.gpo_high = !int_gpo.gpo_high;
+  /// ic.set_int_gpo(int_gpo)?;
+  /// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+  /// ```
+  pub fn get_int_gpo(&mut self) -> Result<IntGpo, LinuxI2CError> {
+    debug::scope!(self, "get_int_gpo()");
+
+    let value = self.read_register(Register::INT_GPO)?;
+    let int_gpo = IntGpo {
+      int_as_gpo: Mask::INT_CONF.is_set(value),
+      gpo_high: Mask::GPO.is_set(value),
+      int_gpo_high: Mask::INT_GPO.is_set(value),
+    };
+
+    Ok(int_gpo)
+  }
+
+  /// Set [INT/GPO](IntGpo) pin configuration for the IC.
+  ///
+  /// Overrides all existing settings.
+  pub fn set_int_gpo(&mut self, int_gpo: IntGpo) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_int_gpo({:?})", int_gpo);
+
+    let int_conf = Mask::INT_CONF.with(int_gpo.int_as_gpo as u8);
+    let gpo = Mask::GPO.with(int_gpo.gpo_high as u8);
+    let int_gpo = Mask::INT_GPO.with(int_gpo.int_gpo_high as u8);
+
+    self.write_register(Register::INT_GPO, int_conf | gpo | int_gpo)
+  }
+
+  /// Write a temperature (in °C) for the IC to use instead of its internal
+  /// sensor's reading, e.g. one measured by a better placed external sensor.
+  ///
+  /// The written value is only used while `SEL_EXT_TEMP` is set in
+  /// `TEMP ADC CONTROL` (see [`Self::set_manual_temperature_enabled`]);
+  /// otherwise the internal sensor's reading is used.
+  pub fn set_manual_temperature(
+    &mut self,
+    raw: i8,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_manual_temperature({})", raw);
+
+    // Stored in two's complement.
+    self.write_register(Register::TEMPERATURE_WRITE, raw as u8)
+  }
+
+  /// Select whether the IC uses the temperature written via
+  /// [`Self::set_manual_temperature`] (`SEL_EXT_TEMP` set) or its internal
+  /// sensor's reading.
+  pub fn set_manual_temperature_enabled(
+    &mut self,
+    enabled: bool,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_manual_temperature_enabled({})", enabled);
+
+    let current_value = self.read_register(Register::TEMP_ADC_CONTROL)?;
+    let new_value = Mask::SEL_EXT_TEMP.apply(enabled as u8, current_value);
+    if new_value != current_value {
+      self.write_register(Register::TEMP_ADC_CONTROL, new_value)?;
+    }
+
+    Ok(())
+  }
+
+  /// Read the [temperature sensor configuration](TempAdcControl) from the
+  /// IC, e.g. to confirm it survived a brownout.
+  pub fn get_temp_adc_control(
+    &mut self,
+  ) -> Result<TempAdcControl, LinuxI2CError> {
+    debug::scope!(self, "get_temp_adc_control()");
+
+    let value = self.read_register(Register::TEMP_ADC_CONTROL)?;
+    let control = TempAdcControl {
+      measurement_busy: Mask::TEMP_MEAS_BUSY.is_set(value),
+      sensor_enabled: Mask::EN_TEMP_SENSOR.is_set(value),
+      continuous_conversion: Mask::CONTINUOUS_CONV.is_set(value),
+      external_temperature: Mask::SEL_EXT_TEMP.is_set(value),
+    };
+
+    Ok(control)
   }
 
   /// Set the Pulse-Width Modulation (PWM) value for the specified [`Channel`].
@@ -133,9 +559,200 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Read the Pulse-Width Modulation (PWM) value for the specified
+  /// [`Channel`].
+  pub fn get_channel_pwm(
+    &mut self,
+    channel: Channel,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_channel_pwm(channel: {:?})", channel);
+
+    self.read_register(Register::pwm_for(channel))
+  }
+
+  /// Same as [`Self::set_channel_pwm`], applying [gamma
+  /// correction](gamma::correct) to `value` first.
+  pub fn set_channel_pwm_gamma(
+    &mut self,
+    channel: Channel,
+    value: u8,
+    gamma: f32,
+  ) -> Result<(), LinuxI2CError> {
+    self.set_channel_pwm(channel, gamma::correct(value, gamma))
+  }
+
+  /// Set the Pulse-Width Modulation (PWM) values for each channel of the
+  /// specified [`RgbGroup`].
+  pub fn set_rgb(
+    &mut self,
+    group: &RgbGroup,
+    r: u8,
+    g: u8,
+    b: u8,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "set_rgb(group: {:?}, r: {}, g: {}, b: {})",
+      group,
+      r,
+      g,
+      b
+    );
+
+    self.write_register(Register::pwm_for(group.r), r)?;
+    self.write_register(Register::pwm_for(group.g), g)?;
+    self.write_register(Register::pwm_for(group.b), b)?;
+
+    Ok(())
+  }
+
+  /// Same as [`Self::set_rgb`], taking the color as a `0xRRGGBB` value.
+  ///
+  /// The most significant byte is ignored.
+  pub fn set_rgb_hex(
+    &mut self,
+    group: &RgbGroup,
+    rgb: u32,
+  ) -> Result<(), LinuxI2CError> {
+    let [_, r, g, b] = rgb.to_be_bytes();
+    self.set_rgb(group, r, g, b)
+  }
+
+  /// Set the Pulse-Width Modulation (PWM) values for all channels, ordered D1
+  /// through D9.
+  ///
+  /// `D1 PWM` through `D9 PWM` are contiguous, so this is a single I2C write
+  /// when `EN_AUTO_INCR` is set (see [`Self::write_registers`]).
+  pub fn set_all_pwm(&mut self, values: [u8; 9]) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_all_pwm({:?})", values);
+
+    self.write_contiguous(Register::D1_PWM, &values)
+  }
+
+  /// Set the PWM value of each of the specified channels to its `base` value
+  /// scaled by `intensity / 255`, preserving the ratios between channels.
+  ///
+  /// A software equivalent of a master fader (see [`Self::assign_to_fader`])
+  /// that doesn't need the channels to be assigned a [`Fader`]. `channels`
+  /// and `base` must be the same length.
+  pub fn set_group_intensity(
+    &mut self,
+    channels: &[Channel],
+    base: &[u8],
+    intensity: u8,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "set_group_intensity(channels: {:?}, base: {:?}, intensity: {})",
+      channels,
+      base,
+      intensity
+    );
+
+    if channels.len() != base.len() {
+      return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+        "invalid number of base values ({}); expected {}",
+        base.len(),
+        channels.len()
+      ))));
+    }
+
+    for (channel, base) in channels.iter().zip(base) {
+      self.set_channel_pwm(*channel, scale_intensity(*base, intensity))?;
+    }
+
+    Ok(())
+  }
+
+  /// Fade the specified [`Channel`] from one PWM value to another over
+  /// `duration`, writing `steps` linearly interpolated values from the host
+  /// (no programming engine involved).
+  ///
+  /// When `gamma` is provided, [gamma correction](gamma::correct) is applied
+  /// to every interpolated value. Blocks until the fade completes; the final
+  /// value written is always `to` (gamma-corrected). A `steps` value of 0 is
+  /// treated as 1 (i.e. jump to `to` after `duration`).
+  pub fn fade_channel(
+    &mut self,
+    channel: Channel,
+    from: u8,
+    to: u8,
+    duration: Duration,
+    steps: u32,
+    gamma: Option<f32>,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "fade_channel(channel: {:?}, from: {}, to: {}, duration: {:?}, steps: {}, gamma: {:?})",
+      channel,
+      from,
+      to,
+      duration,
+      steps,
+      gamma
+    );
+
+    let steps = steps.max(1);
+    let start = Instant::now();
+    for step in 1..=steps {
+      let mut pwm = interpolate(from, to, step, steps);
+      if let Some(gamma) = gamma {
+        pwm = gamma::correct(pwm, gamma);
+      }
+
+      sleep_until(start + duration * step / steps);
+      self.write_register(Register::pwm_for(channel), pwm)?;
+    }
+
+    Ok(())
+  }
+
+  /// Same as [`Self::fade_channel`] for all channels at once (ordered D1
+  /// through D9), e.g. to transition between two colors.
+  ///
+  /// Every step is pushed with [`Self::set_all_pwm`].
+  pub fn fade_all(
+    &mut self,
+    from: [u8; 9],
+    to: [u8; 9],
+    duration: Duration,
+    steps: u32,
+    gamma: Option<f32>,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "fade_all(from: {:?}, to: {:?}, duration: {:?}, steps: {}, gamma: {:?})",
+      from,
+      to,
+      duration,
+      steps,
+      gamma
+    );
+
+    let steps = steps.max(1);
+    let start = Instant::now();
+    for step in 1..=steps {
+      let mut values = [0; 9];
+      for (index, value) in values.iter_mut().enumerate() {
+        *value = interpolate(from[index], to[index], step, steps);
+        if let Some(gamma) = gamma {
+          *value = gamma::correct(*value, gamma);
+        }
+      }
+
+      sleep_until(start + duration * step / steps);
+      self.set_all_pwm(values)?;
+    }
+
+    Ok(())
+  }
+
   /// Set the current value for the specified [`Channel`].
   ///
-  /// Current controls luminous intensity (brightness).
+  /// Current controls luminous intensity (brightness), in steps of 0.1mA
+  /// (i.e. up to 25.5mA); see [`Self::set_channel_current_ma`].
+  ///
+  /// Subject to [`Self::current_limit`], if set.
   pub fn set_channel_current(
     &mut self,
     channel: Channel,
@@ -148,11 +765,63 @@ impl LP55231 {
       current
     );
 
+    let current = self.limit_current(current)?;
     self.write_register(Register::current_control_for(channel), current)?;
 
     Ok(())
   }
 
+  /// Same as [`Self::set_channel_current`], taking the current in milliamps
+  /// (see [`current_from_ma`]).
+  ///
+  /// `ma` must be in range \[0:25.5\].
+  pub fn set_channel_current_ma(
+    &mut self,
+    channel: Channel,
+    ma: f32,
+  ) -> Result<(), LinuxI2CError> {
+    let current = current_from_ma(ma).ok_or_else(|| {
+      LinuxI2CError::Io(std::io::Error::other(format!(
+        "invalid current ({}mA); must be in range [0:{}]",
+        ma, MAX_CURRENT_MA
+      )))
+    })?;
+
+    self.set_channel_current(channel, current)
+  }
+
+  /// Read the current value for the specified [`Channel`].
+  pub fn get_channel_current(
+    &mut self,
+    channel: Channel,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_channel_current(channel: {:?})", channel);
+
+    self.read_register(Register::current_control_for(channel))
+  }
+
+  /// Set the current values for all channels, ordered D1 through D9.
+  ///
+  /// `D1 CURRENT CONTROL` through `D9 CURRENT CONTROL` are contiguous, so this
+  /// is a single I2C write when `EN_AUTO_INCR` is set (see
+  /// [`Self::write_registers`]).
+  ///
+  /// Subject to [`Self::current_limit`], if set; no value is written if any
+  /// is rejected.
+  pub fn set_all_current(
+    &mut self,
+    values: [u8; 9],
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_all_current({:?})", values);
+
+    let mut limited = [0; 9];
+    for (index, value) in values.iter().enumerate() {
+      limited[index] = self.limit_current(*value)?;
+    }
+
+    self.write_contiguous(Register::D1_CURRENT_CONTROL, &limited)
+  }
+
   /// Enable or disable logarithmic brightness for the specified [`Channel`].
   pub fn set_log_brightness(
     &mut self,
@@ -177,6 +846,18 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Test whether logarithmic brightness is enabled for the specified
+  /// [`Channel`].
+  pub fn get_log_brightness(
+    &mut self,
+    channel: Channel,
+  ) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "get_log_brightness(channel: {:?})", channel);
+
+    let value = self.read_register(Register::control_for(channel))?;
+    Ok(Mask::LOG_EN.is_set(value))
+  }
+
   /// Enable or disable radiometric dimming for the specified [`Channel`].
   pub fn set_ratiometric_dimming(
     &mut self,
@@ -213,18 +894,66 @@ impl LP55231 {
     Ok(())
   }
 
-  /// Enable or disable the specified [`Channel`].
-  pub fn set_channel_enabled(
+  /// Test whether radiometric dimming is enabled for the specified
+  /// [`Channel`].
+  pub fn get_ratiometric_dimming(
     &mut self,
     channel: Channel,
-    enabled: bool,
-  ) -> Result<(), LinuxI2CError> {
-    debug::scope!(
-      self,
-      "set_channel_enabled(channel: {:?}, enabled: {})",
-      channel,
-      enabled
-    );
+  ) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "get_ratiometric_dimming(channel: {:?})", channel);
+
+    let register = if channel == Channel::D9 {
+      Register::OUTPUT_DIRECT_RATIOMETRIC_MSB
+    } else {
+      Register::OUTPUT_DIRECT_RATIOMETRIC_LSB
+    };
+    let value = self.read_register(register)?;
+    Ok(Mask::ratiometric_dimming_for(channel).is_set(value))
+  }
+
+  /// Read whether ratiometric dimming is enabled for each [`Channel`],
+  /// ordered D1 through D9.
+  ///
+  /// Reads both `OUTPUT DIRECT/RATIOMETRIC` registers; inverse of
+  /// [`Self::set_ratiometric`].
+  pub fn get_ratiometric(&mut self) -> Result<[bool; 9], LinuxI2CError> {
+    debug::scope!(self, "get_ratiometric()");
+
+    let msb = self.read_register(Register::OUTPUT_DIRECT_RATIOMETRIC_MSB)?;
+    let lsb = self.read_register(Register::OUTPUT_DIRECT_RATIOMETRIC_LSB)?;
+
+    Ok(join_channel_bits(msb, lsb, Mask::ratiometric_dimming_for))
+  }
+
+  /// Enable or disable ratiometric dimming for every [`Channel`] at once,
+  /// ordered D1 through D9.
+  ///
+  /// Both `OUTPUT DIRECT/RATIOMETRIC` registers are computed up front and
+  /// written directly (in a single I2C write when `EN_AUTO_INCR` is set),
+  /// without read-modify-write.
+  pub fn set_ratiometric(
+    &mut self,
+    ratiometric: [bool; 9],
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_ratiometric({:?})", ratiometric);
+
+    let (msb, lsb) =
+      split_channel_bits(ratiometric, Mask::ratiometric_dimming_for);
+    self.write_contiguous(Register::OUTPUT_DIRECT_RATIOMETRIC_MSB, &[msb, lsb])
+  }
+
+  /// Enable or disable the specified [`Channel`].
+  pub fn set_channel_enabled(
+    &mut self,
+    channel: Channel,
+    enabled: bool,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "set_channel_enabled(channel: {:?}, enabled: {})",
+      channel,
+      enabled
+    );
 
     // D9 has its own register; no need to read-modify-write.
     if channel == Channel::D9 {
@@ -243,6 +972,69 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Enable the specified channels, disabling every other [`Channel`].
+  ///
+  /// Unlike [`Self::set_channel_enabled`], both `OUTPUT ON/OFF CONTROL`
+  /// registers are computed up front and written directly (in a single I2C
+  /// write when `EN_AUTO_INCR` is set), without read-modify-write.
+  pub fn set_enabled_channels(
+    &mut self,
+    channels: &[Channel],
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_enabled_channels({:?})", channels);
+
+    let mut on_off = [false; 9];
+    for channel in channels {
+      on_off[*channel as usize] = true;
+    }
+
+    self.set_channel_on_off(on_off)
+  }
+
+  /// Read whether each [`Channel`] is enabled, ordered D1 through D9.
+  ///
+  /// Reads both `OUTPUT ON/OFF CONTROL` registers; inverse of
+  /// [`Self::set_channel_on_off`].
+  pub fn get_channel_on_off(&mut self) -> Result<[bool; 9], LinuxI2CError> {
+    debug::scope!(self, "get_channel_on_off()");
+
+    let msb = self.read_register(Register::OUTPUT_ON_OFF_CONTROL_MSB)?;
+    let lsb = self.read_register(Register::OUTPUT_ON_OFF_CONTROL_LSB)?;
+
+    Ok(join_channel_bits(msb, lsb, Mask::on_off_for))
+  }
+
+  /// Enable or disable every [`Channel`] at once, ordered D1 through D9.
+  ///
+  /// Both `OUTPUT ON/OFF CONTROL` registers are computed up front and written
+  /// directly (in a single I2C write when `EN_AUTO_INCR` is set), without
+  /// read-modify-write.
+  pub fn set_channel_on_off(
+    &mut self,
+    on_off: [bool; 9],
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_channel_on_off({:?})", on_off);
+
+    let (msb, lsb) = split_channel_bits(on_off, Mask::on_off_for);
+    self.write_contiguous(Register::OUTPUT_ON_OFF_CONTROL_MSB, &[msb, lsb])
+  }
+
+  /// Test whether the specified [`Channel`] is enabled.
+  pub fn is_channel_enabled(
+    &mut self,
+    channel: Channel,
+  ) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "is_channel_enabled(channel: {:?})", channel);
+
+    let register = if channel == Channel::D9 {
+      Register::OUTPUT_ON_OFF_CONTROL_MSB
+    } else {
+      Register::OUTPUT_ON_OFF_CONTROL_LSB
+    };
+    let value = self.read_register(register)?;
+    Ok(Mask::on_off_for(channel).is_set(value))
+  }
+
   /// Assign the specified [`Channel`] to the specified [`Fader`].
   /// Removes [`Fader`] associations if `None` is supplied as an argument.
   ///
@@ -273,6 +1065,26 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Read the [`Fader`] the specified [`Channel`] is assigned to, if any.
+  ///
+  /// Inverse of [`Self::assign_to_fader`].
+  pub fn get_fader_assignment(
+    &mut self,
+    channel: Channel,
+  ) -> Result<Option<Fader>, LinuxI2CError> {
+    debug::scope!(self, "get_fader_assignment(channel: {:?})", channel);
+
+    let value = self.read_register(Register::control_for(channel))?;
+
+    // 00 - none, 01 - F1, 02 - F2, 03 - F3
+    let fader = Mask::MAPPING
+      .value(value)
+      .checked_sub(1)
+      .and_then(|index| Fader::try_from(index).ok());
+
+    Ok(fader)
+  }
+
   /// Adjust the intensity of the specified [`Fader`].
   ///
   /// Will result in the adjustment of the intensity of every [`Channel`]
@@ -292,6 +1104,19 @@ impl LP55231 {
     self.write_register(Register::intensity_for(fader), intensity)
   }
 
+  /// Read the current intensity of the specified [`Fader`].
+  pub fn get_fader_intensity(
+    &mut self,
+    fader: Fader,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_fader_intensity(fader: {:?})", fader);
+
+    self.read_register(Register::intensity_for(fader))
+  }
+
+  /// Clear pending interrupts for *all* engines, discarding them.
+  ///
+  /// See [`Self::take_interrupts`] to find out which engines raised them.
   pub fn clear_interrupt(&mut self) -> Result<(), LinuxI2CError> {
     debug::scope!(self, "clear_interrupt()");
 
@@ -300,6 +1125,184 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Read the [status](Status) flags from the IC.
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears any pending engine interrupts (see
+  /// [`Self::clear_interrupt`]), so the interrupt flags are only reported once.
+  pub fn get_status(&mut self) -> Result<Status, LinuxI2CError> {
+    debug::scope!(self, "get_status()");
+
+    let value = self.read_register(Register::STATUS_INTERRUPT)?;
+    let status = Status {
+      ledtest_measurement_done: Mask::LEDTEST_MEAS_DONE.is_set(value),
+      mask_busy: Mask::MASK_BUSY.is_set(value),
+      startup_busy: Mask::STARTUP_BUSY.is_set(value),
+      engine_busy: Mask::ENGINE_BUSY.is_set(value),
+      ext_clk_used: Mask::EXT_CLK_USED.is_set(value),
+      engine1_interrupt: Mask::ENG1_INT.is_set(value),
+      engine2_interrupt: Mask::ENG2_INT.is_set(value),
+      engine3_interrupt: Mask::ENG3_INT.is_set(value),
+    };
+
+    Ok(status)
+  }
+
+  /// Read and clear all pending engine interrupts, returning them along with
+  /// the other [status](Status) flags.
+  ///
+  /// Reading `STATUS_INTERRUPT` is destructive: interrupts for *all* engines
+  /// are cleared at once, so each interrupt is only ever reported by a single
+  /// read. Capture every engine's flag from the returned status, rather than
+  /// checking engines one at a time (e.g. via [`Self::engine_interrupted`]),
+  /// to avoid losing interrupts.
+  pub fn take_interrupts(&mut self) -> Result<Status, LinuxI2CError> {
+    debug::scope!(self, "take_interrupts()");
+
+    self.get_status()
+  }
+
+  /// Test whether the specified [`Engine`] raised an interrupt (i.e. executed
+  /// an [`Instruction::int`] or an [`Instruction::end`] with interrupt).
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears pending interrupts for *all*
+  /// engines; use [`Self::get_status`] to check every engine in one read.
+  pub fn engine_interrupted(
+    &mut self,
+    engine: Engine,
+  ) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "engine_interrupted(engine: {:?})", engine);
+
+    let value = self.read_register(Register::STATUS_INTERRUPT)?;
+    Ok(Mask::int_for(engine).is_set(value))
+  }
+
+  /// Test whether the IC is running off an external clock (`EXT_CLK_USED`),
+  /// e.g. to confirm [`ClockSelection::Automatic`] detected it rather than
+  /// falling back to the internal oscillator.
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears pending interrupts for *all*
+  /// engines; see [`Self::take_interrupts`].
+  pub fn is_external_clock_used(&mut self) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "is_external_clock_used()");
+
+    let value = self.read_register(Register::STATUS_INTERRUPT)?;
+    Ok(Mask::EXT_CLK_USED.is_set(value))
+  }
+
+  /// Test whether the IC is still initializing after a reset or power up
+  /// (`STARTUP_BUSY`); see [`Self::wait_while_startup_busy`].
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears pending interrupts for *all*
+  /// engines; see [`Self::take_interrupts`].
+  pub fn is_startup_busy(&mut self) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "is_startup_busy()");
+
+    let value = self.read_register(Register::STATUS_INTERRUPT)?;
+    Ok(Mask::STARTUP_BUSY.is_set(value))
+  }
+
+  /// Test whether the `MASK_BUSY` status bit is set.
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears pending interrupts for *all*
+  /// engines; see [`Self::take_interrupts`].
+  pub fn is_mask_busy(&mut self) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "is_mask_busy()");
+
+    let value = self.read_register(Register::STATUS_INTERRUPT)?;
+    Ok(Mask::MASK_BUSY.is_set(value))
+  }
+
+  /// Wait for the IC to assert its INT pin (active low), sampling `pin` at
+  /// intervals of specified duration, for up to `timeout`.
+  ///
+  /// Requires the INT/GPO pin to be configured as an interrupt output (see
+  /// [`Self::set_int_gpo`]). Once asserted, reads the [status](Status), which
+  /// clears the interrupt and releases the pin.
+  ///
+  /// [`InputPin`] only exposes the pin level, so this samples the pin rather
+  /// than waiting for an edge: an interrupt is noticed up to `poll_interval`
+  /// after it's raised (plus scheduling latency), and the thread wakes up
+  /// once per `poll_interval` while waiting. Unlike polling
+  /// [`Self::get_status`], no I2C traffic is generated while waiting. For
+  /// edge-triggered waits, block on the host's GPIO line events instead and
+  /// call [`Self::take_interrupts`] once the line fires.
+  ///
+  /// Returns an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut) if
+  /// the pin isn't asserted once `timeout` elapses.
+  pub fn wait_for_interrupt<P>(
+    &mut self,
+    pin: &mut P,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<Status, LinuxI2CError>
+  where
+    P: InputPin,
+    P::Error: std::fmt::Debug,
+  {
+    debug::scope!(self, "wait_for_interrupt(timeout: {:?})", timeout);
+
+    let deadline = Instant::now() + timeout;
+    loop {
+      let asserted = pin.is_low().map_err(|error| {
+        LinuxI2CError::Io(std::io::Error::other(format!(
+          "failed to read INT pin: {:?}",
+          error
+        )))
+      })?;
+      if asserted {
+        return self.get_status();
+      }
+      if Instant::now() >= deadline {
+        return Err(LinuxI2CError::Io(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!("INT not asserted after {:?}", timeout),
+        )));
+      }
+      sleep(poll_interval);
+    }
+  }
+
+  /// Wait for the specified [`Engine`] to raise an interrupt (i.e. execute an
+  /// [`Instruction::int`] or an [`Instruction::end`] with interrupt), polling
+  /// its `ENGx_INT` flag at intervals of specified duration, for up to
+  /// `timeout`.
+  ///
+  /// The interrupt is cleared on return. Every poll reads `STATUS_INTERRUPT`,
+  /// which clears pending interrupts for *all* engines, so interrupts raised
+  /// by other engines while waiting are discarded; use
+  /// [`Self::take_interrupts`] to react to several engines.
+  ///
+  /// Returns an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut) if
+  /// the engine hasn't raised an interrupt once `timeout` elapses.
+  pub fn wait_for_engine_interrupt(
+    &mut self,
+    engine: Engine,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "wait_for_engine_interrupt(engine: {:?}, timeout: {:?})",
+      engine,
+      timeout
+    );
+
+    let deadline = Instant::now() + timeout;
+    loop {
+      let value = self.read_register(Register::STATUS_INTERRUPT)?;
+      if Mask::int_for(engine).is_set(value) {
+        return Ok(());
+      }
+      if Instant::now() >= deadline {
+        return Err(LinuxI2CError::Io(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!("no interrupt from engine {:?} after {:?}", engine, timeout),
+        )));
+      }
+      sleep(poll_interval);
+    }
+  }
+
   /// Load the specified program.
   ///
   /// Accepts up to [`MAX_INSTRUCTIONS`], writing them over as many pages as
@@ -317,6 +1320,8 @@ impl LP55231 {
   ///
   /// After the program is loaded all `ENG* PROG START ADDR` values reset to
   /// default (see [`Self::set_engine_entry_point`]).
+  ///
+  /// Instructions aren't checked for mistakes; see [`program::validate`].
   pub fn load_program(
     &mut self,
     instructions: &[Instruction],
@@ -325,205 +1330,791 @@ impl LP55231 {
 
     validate_total_instruction_count(instructions)?;
 
-    // 1. Set all engines to _load program_ mode.
-    //
-    // From the spec (section 7.6.2, page 28):
-    //  "Load program mode can be entered from the disabled mode only.
-    //  Entering load program mode from the run program mode is not allowed."
-    //
-    // Not clear in spec, but all engines must be disabled.
-    self.set_all_engines_mode(EngineMode::Disabled)?;
-    // From the spec (section 7.6.3, page 37):
-    //  "in order to access program memory the operation mode needs to be
-    //  load program"
-    //
-    // Not clear in spec, but all engines must be in load mode, otherwise
-    // writes do not work (read-after-write returns empty program registers).
-    self.set_all_engines_mode(EngineMode::LoadProgram)?;
-
-    // 2. Wait until clear to enter load mode; from the spec (7.6.2, pg 28):
-    //  "Serial bus master should check the busy bit before writing to program
-    //  memory or allow at least 1ms delay after entering to load mode before
-    //  memory write (...)"
-    let poll_interval = Duration::from_millis(1);
-    self.wait_while_engine_busy(poll_interval)?;
-    sleep(poll_interval * 10);
+    // 1-2. Set all engines to _load program_ mode and wait until clear to
+    // write to program memory.
+    self.enter_load_mode()?;
 
-    // optional step: ensure auto-increment is set to allow single I2C write
-    // per program page (vs `2 * instructions.len()` writes if writing
-    // instructions one-by-one).
-    //
-    // From the spec (section 7.5.2.3, page 20):
-    //  "The auto-increment feature allows writing several consecutive
-    //  registers within one transmission"
-    let auto_incr = false;
-    // TODO uncomment and change above to true.
-    // let mut misc = self.get_misc_settings()?;
-    // if !misc.auto_increment_enabled {
-    //   misc.auto_increment_enabled = true;
-    //   self.set_misc_settings(misc)?;
-    // }
-
-    // 3. Break program into pages of 16 instructions and write each page.
+    // 3. Break program into pages of 16 instructions and write each page;
+    // in a single I2C write per page if auto-increment is enabled.
+    let at_once = self.is_auto_increment_enabled()?;
     let pages: Vec<&[Instruction]> = instructions.chunks(16).collect();
     for (page_num, page_instructions) in pages.iter().enumerate() {
-      self.write_program_page(page_num as u8, page_instructions, auto_incr)?;
+      self.write_program_page(page_num as u8, page_instructions, at_once)?;
     }
 
     // 4. Set all engines back to disabled.
-    self.set_all_engines_mode(EngineMode::Disabled)
+    self.enter_disabled_mode()
   }
 
-  /// Read a single program [`Instruction`] at the specified `index`, from the
-  /// current page (i.e. the page selected via
-  /// [PROG MEM PAGE SEL](Register::PROG_MEM_PAGE_SEL) register).
-  pub fn read_program_instruction(
+  /// Same as [`Self::load_program`] for the programs of all engines, then
+  /// set each engine's entry point to the start of its program (see
+  /// [`ProgramLayout`]).
+  pub fn load_program_layout(
     &mut self,
-    index: u8,
-  ) -> Result<Instruction, LinuxI2CError> {
-    validate_instruction_index(index)?;
-
-    let register = (Register::PROG_MEM_BASE as u8) + (index * 2);
-    let msb = self.device.smbus_read_byte_data(register)?;
-    let lsb = self.device.smbus_read_byte_data(register + 1)?;
-    debug::text!(
+    layout: &ProgramLayout,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
       self,
-      "[{:02}] << {:02x} & {:02x} {:08b} {:08b} (0x{:02x}{:02x})",
-      index,
-      register,
-      register + 1,
-      msb,
-      lsb,
-      msb,
-      lsb,
+      "load_program_layout(entry_points: {:?})",
+      layout.entry_points()
     );
-    Ok(Instruction { msb, lsb })
+
+    self.load_program(layout.instructions())?;
+    self.set_engine_entry_points(layout.entry_points())
   }
 
-  /// Write up to [`INSTRUCTIONS_PER_PAGE`] program [instructions](Instruction)
-  /// to the specified `page`.
+  /// Read back the loaded program and compare it against `expected`.
   ///
-  /// Arguments:
-  /// * `page` - The page number to write. Must be in range \[0:5\]
-  /// * `instructions` - List of instructions to write.
-  /// * `at_once` - Whether to write all instructions in a single I2C write
-  /// or use individual writes (each instruction is 16 bytes, which could result
-  /// in up to 32 writes).
+  /// Program-level counterpart to [`Self::verify_writes`], catching
+  /// corruption during uploads. Like [`Self::load_program`], enters load mode
+  /// to access program memory and leaves all engines disabled.
   ///
-  /// `at_once` Should only be set to true if the device is configured with
-  /// `EN_AUTO_INCR` (see [`Self::set_misc_settings`]).
-  pub fn write_program_page(
+  /// Fails with the first mismatching instruction, if any.
+  pub fn verify_program(
     &mut self,
-    page: u8,
-    instructions: &[Instruction],
-    at_once: bool,
-  ) -> Result<(), LinuxI2CError> {
-    validate_page(page)?;
-    validate_per_page_instruction_count(instructions)?;
-
-    debug::scope!(
-      self,
-      "write_program_page(page: {}, [{} instructions])",
-      page,
-      instructions.len()
-    );
-
-    // Set the page number...
-    self.write_register(Register::PROG_MEM_PAGE_SEL, page)?;
-    // ... and write the instructions.
-    if at_once {
-      panic!("not yet implemented");
-      // TODO test single I2C writes relying on auto-increment
-      // self.device.smbus_write_block_data(Register::PROG_MEM_BASE, ???)
-    } else {
-      for (index, instruction) in instructions.iter().enumerate() {
-        self.write_program_instruction(index as u8, instruction)?;
+    expected: &[Instruction],
+  ) -> Result<(), VerifyError> {
+    debug::scope!(self, "verify_program([{} instructions])", expected.len());
+
+    validate_total_instruction_count(expected)?;
+
+    self.enter_load_mode()?;
+    let at_once = self.is_auto_increment_enabled()?;
+    let mut actual = Vec::with_capacity(expected.len());
+    let page_count = expected.len().div_ceil(INSTRUCTIONS_PER_PAGE as usize);
+    for page in 0..page_count {
+      actual.extend(self.read_program_page(page as u8, at_once)?);
+    }
+    self.enter_disabled_mode()?;
+
+    for (index, (expected, actual)) in expected.iter().zip(&actual).enumerate()
+    {
+      if expected.as_u16() != actual.as_u16() {
+        return Err(VerifyError::Mismatch {
+          index,
+          expected: expected.decode(),
+          actual: actual.decode(),
+        });
       }
     }
 
     Ok(())
   }
 
-  /// Write a single program [`Instruction`] at the specified index, to the
-  /// current page (i.e. the page currently selected via
-  /// [PROG MEM PAGE SEL](Register::PROG_MEM_PAGE_SEL)).
-  pub fn write_program_instruction(
+  /// Replace a single instruction of the loaded program, at the specified
+  /// absolute address (i.e. across all pages).
+  ///
+  /// Cheaper than reloading the whole program (see [`Self::load_program`]):
+  /// enters load mode, writes the instruction, then restores the engine modes
+  /// and entry points that were set before the call.
+  ///
+  /// NB: Running engines are interrupted while the instruction is written.
+  pub fn patch_instruction(
     &mut self,
-    index: u8,
+    address: u8,
     instr: &Instruction,
   ) -> Result<(), LinuxI2CError> {
-    validate_instruction_index(index)?;
+    validate_address(address)?;
 
-    let register = (Register::PROG_MEM_BASE as u8) + (index * 2);
-    // TODO single u16 write (requires auto-increment)
-    self.device.smbus_write_byte_data(register, instr.msb)?;
-    self.device.smbus_write_byte_data(register + 1, instr.lsb)?;
-    debug::text!(
+    debug::scope!(
       self,
-      "[{:02}] >> {:02x} & {:02x} {:08b} {:08b} (0x{:02x}{:02x})",
-      index,
-      register,
-      register + 1,
-      instr.msb,
-      instr.lsb,
-      instr.msb,
-      instr.lsb,
+      "patch_instruction(address: {}, 0x{:04x})",
+      address,
+      instr.as_u16()
     );
-    Ok(())
-  }
-
-  /// Read a program page.
-  ///
-  /// Each page contains up to [`INSTRUCTIONS_PER_PAGE`]
-  /// [instructions](Instruction).
-  pub fn read_program_page(
-    &mut self,
-    page: u8,
-    at_once: bool,
-  ) -> Result<Vec<Instruction>, LinuxI2CError> {
-    validate_page(page)?;
 
-    debug::scope!(self, "read_program_page(page: {})", page);
+    let modes = self.read_register(Register::ENGINE_CNTRL_2)?;
+    let mut entry_points = [0; 3];
+    for (engine, entry_point) in Engine::all().iter().zip(&mut entry_points) {
+      *entry_point = self.get_engine_entry_point(*engine)?;
+    }
 
-    self.write_register(Register::PROG_MEM_PAGE_SEL, page)?;
-    let mut instructions: Vec<Instruction> = vec![];
-    if at_once {
-      // TODO read whole page at once
-      panic!("not implemented")
+    self.enter_load_mode()?;
+    self.select_page(address / INSTRUCTIONS_PER_PAGE)?;
+    let index = address % INSTRUCTIONS_PER_PAGE;
+    if self.is_auto_increment_enabled()? {
+      self.write_program_instruction_word(index, instr)?;
     } else {
-      for i in 0..16 {
-        let instruction = self.read_program_instruction(i)?;
-        instructions.push(instruction);
-      }
+      self.write_program_instruction(index, instr)?;
     }
+    // Engines must go through disabled mode to leave load program mode.
+    self.enter_disabled_mode()?;
 
-    Ok(instructions)
+    for (engine, entry_point) in Engine::all().iter().zip(entry_points) {
+      self.set_engine_entry_point(*engine, entry_point)?;
+    }
+    self.write_register(Register::ENGINE_CNTRL_2, modes)
   }
 
-  /// Set the starting address for the specified [`Engine`] program instructions.
+  /// Load a program for the specified [`Engine`] only, starting at
+  /// `entry_point`, and set it as the engine's entry point.
   ///
-  /// Defaults:
-  /// - Engine 1: 0
-  /// - Engine 2: 8
-  /// - Engine 3: 16
-  pub fn set_engine_entry_point(
+  /// Unlike [`Self::load_program`], only the instructions in range
+  /// `entry_point..entry_point + instructions.len()` are written; the rest of
+  /// program memory, and the modes and entry points of the other engines, are
+  /// restored once the program is written. The target engine is left
+  /// disabled, and must be manually switched to run mode.
+  ///
+  /// NB: Running engines are interrupted while the program is written.
+  pub fn load_engine_program(
     &mut self,
     engine: Engine,
+    instructions: &[Instruction],
     entry_point: u8,
   ) -> Result<(), LinuxI2CError> {
-    // TODO validate entry_point value fits 7 bits (i.e. <= MASK_ADDR)
     debug::scope!(
       self,
-      "set_engine_entry_point(engine: {:?}, entry_point: {})",
+      "load_engine_program(engine: {:?}, [{} instructions], entry_point: {})",
       engine,
+      instructions.len(),
       entry_point
     );
 
-    self.write_register(Register::program_start_for(engine), entry_point)
-  }
+    validate_entry_point(entry_point)?;
+    let end = entry_point as usize + instructions.len();
+    if end > MAX_INSTRUCTIONS as usize {
+      return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+        "too many instructions ({}) at entry point {}; limit is {}",
+        instructions.len(),
+        entry_point,
+        MAX_INSTRUCTIONS - entry_point
+      ))));
+    }
 
-  /// Set program counter value for the specified [`Engine`].
-  ///
+    let modes = self.read_register(Register::ENGINE_CNTRL_2)?;
+    let mut entry_points = [0; 3];
+    for (engine, entry_point) in Engine::all().iter().zip(&mut entry_points) {
+      *entry_point = self.get_engine_entry_point(*engine)?;
+    }
+
+    self.enter_load_mode()?;
+    for (offset, instruction) in instructions.iter().enumerate() {
+      let address = entry_point + offset as u8;
+      self.select_page(address / INSTRUCTIONS_PER_PAGE)?;
+      self.write_program_instruction(
+        address % INSTRUCTIONS_PER_PAGE,
+        instruction,
+      )?;
+    }
+    // Engines must go through disabled mode to leave load program mode.
+    self.enter_disabled_mode()?;
+
+    entry_points[engine as usize] = entry_point;
+    for (engine, entry_point) in Engine::all().iter().zip(entry_points) {
+      self.set_engine_entry_point(*engine, entry_point)?;
+    }
+    let modes =
+      Mask::mode_for(engine).apply(EngineMode::Disabled.bits(), modes);
+    self.write_register(Register::ENGINE_CNTRL_2, modes)
+  }
+
+  /// Put all engines in LOAD PROGRAM mode, allowing access to program memory.
+  ///
+  /// Load program mode can only be entered from disabled mode, so engines are
+  /// disabled first. Returns once the IC is ready for program memory writes.
+  pub fn enter_load_mode(&mut self) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "enter_load_mode()");
+
+    // From the spec (section 7.6.2, page 28):
+    //  "Load program mode can be entered from the disabled mode only.
+    //  Entering load program mode from the run program mode is not allowed."
+    //
+    // Not clear in spec, but all engines must be disabled.
+    self.set_all_engines_mode(EngineMode::Disabled)?;
+    // From the spec (section 7.6.3, page 37):
+    //  "in order to access program memory the operation mode needs to be
+    //  load program"
+    //
+    // Not clear in spec, but all engines must be in load mode, otherwise
+    // writes do not work (read-after-write returns empty program registers).
+    self.set_all_engines_mode(EngineMode::LoadProgram)?;
+
+    // Wait until clear to write; from the spec (7.6.2, pg 28):
+    //  "Serial bus master should check the busy bit before writing to program
+    //  memory or allow at least 1ms delay after entering to load mode before
+    //  memory write (...)"
+    self.wait_while_engine_busy_timeout(
+      timing::ENGINE_BUSY_POLL_INTERVAL,
+      timing::ENGINE_BUSY_TIMEOUT,
+    )?;
+    sleep(timing::LOAD_MODE_DELAY);
+
+    Ok(())
+  }
+
+  /// Put all engines in RUN PROGRAM mode.
+  ///
+  /// Fails if any engine is in LOAD PROGRAM mode, since the spec disallows
+  /// that transition; use [`Self::enter_disabled_mode`] first.
+  pub fn enter_run_mode(&mut self) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "enter_run_mode()");
+
+    let value = self.read_register(Register::ENGINE_CNTRL_2)?;
+    for engine in Engine::all() {
+      let mode = EngineMode::from(Mask::mode_for(engine).value(value));
+      if mode == EngineMode::LoadProgram {
+        return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+          "engine {:?} is in load program mode; disable it before running",
+          engine
+        ))));
+      }
+    }
+
+    self.set_all_engines_mode(EngineMode::RunProgram)
+  }
+
+  /// Put all engines in DISABLED mode.
+  ///
+  /// Disabled mode can be entered from any mode, and is the only way out of
+  /// LOAD PROGRAM mode.
+  pub fn enter_disabled_mode(&mut self) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "enter_disabled_mode()");
+
+    self.set_all_engines_mode(EngineMode::Disabled)
+  }
+
+  /// Load the specified program and start all engines.
+  ///
+  /// Performs the whole sequence required to run a program:
+  /// 1. [Loads the program](Self::load_program), leaving engines disabled
+  /// 2. Sets the entry point of each engine with a `Some` value in
+  ///    `entry_points` (indexed by [`Engine`]); engines with `None` keep their
+  ///    default entry point
+  /// 3. Puts all engines in run mode
+  /// 4. Sets all engines to free-running execution
+  ///
+  /// Returns once the engines are running.
+  pub fn run_program(
+    &mut self,
+    instructions: &[Instruction],
+    entry_points: [Option<u8>; 3],
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "run_program([{} instructions], entry_points: {:?})",
+      instructions.len(),
+      entry_points
+    );
+
+    self.load_program(instructions)?;
+
+    let engines = Engine::all();
+    for (engine, entry_point) in engines.iter().zip(entry_points) {
+      if let Some(entry_point) = entry_point {
+        self.set_engine_entry_point(*engine, entry_point)?;
+      }
+    }
+
+    // Mode is set before execution control so that all engines are in run
+    // mode before any of them starts executing.
+    self.enter_run_mode()?;
+    for engine in engines {
+      self.set_engine_exec(engine, EngineExec::Free)?;
+    }
+
+    Ok(())
+  }
+
+  /// Start the specified [`Engine`], optionally from a new `entry_point`.
+  ///
+  /// Sequences the register writes so that the engine starts executing only
+  /// once it's fully configured:
+  /// 1. Holds execution, so the engine can't run during the steps below
+  /// 2. If `entry_point` is provided, sets it as both the
+  ///    [entry point](Self::set_engine_entry_point) and the
+  ///    [program counter](Self::set_engine_program_counter)
+  /// 3. Puts the engine in run mode
+  /// 4. Sets the engine to free-running execution
+  ///
+  /// The program must already be loaded (see [`Self::load_program`]).
+  pub fn start_engine(
+    &mut self,
+    engine: Engine,
+    entry_point: Option<u8>,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "start_engine(engine: {:?}, entry_point: {:?})",
+      engine,
+      entry_point
+    );
+
+    self.set_engine_exec(engine, EngineExec::Hold)?;
+    if let Some(entry_point) = entry_point {
+      self.set_engine_entry_point(engine, entry_point)?;
+      self.set_engine_program_counter(engine, entry_point)?;
+    }
+    self.set_engine_mode(engine, EngineMode::RunProgram)?;
+    self.set_engine_exec(engine, EngineExec::Free)
+  }
+
+  /// Halt the specified [`Engine`], reversing [`Self::start_engine`]: holds
+  /// execution first, then puts the engine in halt mode.
+  ///
+  /// The program counter is preserved, so the engine can be resumed with
+  /// `start_engine(engine, None)`.
+  pub fn halt_engine(&mut self, engine: Engine) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "halt_engine(engine: {:?})", engine);
+
+    self.set_engine_exec(engine, EngineExec::Hold)?;
+    self.set_engine_mode(engine, EngineMode::Halt)
+  }
+
+  /// Gracefully stop all programs and outputs.
+  ///
+  /// Disables all engines, clears the PWM value of every [`Channel`] and, if
+  /// `disable_chip` is set, disables the IC.
+  ///
+  /// Engines are disabled first so that running programs can't change PWM
+  /// values after they're cleared.
+  pub fn stop(&mut self, disable_chip: bool) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "stop(disable_chip: {})", disable_chip);
+
+    self.set_all_engines_mode(EngineMode::Disabled)?;
+    self.set_all_pwm([0; 9])?;
+
+    if disable_chip {
+      self.set_enabled(false)?;
+    }
+
+    Ok(())
+  }
+
+  /// Turn every output off, e.g. for an emergency stop.
+  ///
+  /// Disables all engines, clears the PWM value of every [`Channel`] and
+  /// disables every channel, in that order so running programs can't re-light
+  /// outputs. Unlike [`Self::stop`], the IC itself is left enabled.
+  pub fn all_off(&mut self) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "all_off()");
+
+    self.set_all_engines_mode(EngineMode::Disabled)?;
+    self.set_all_pwm([0; 9])?;
+    self.set_enabled_channels(&[])
+  }
+
+  /// Attempt to recover from engines stuck with `ENGINE_BUSY` set (e.g.
+  /// after running a malformed program).
+  ///
+  /// Disables all engines, reads the [status](Status) to clear pending
+  /// interrupts, and waits for `ENGINE_BUSY` to clear. If it's still set
+  /// once [`timing::ENGINE_BUSY_TIMEOUT`] elapses and `reset_if_busy` is set,
+  /// [resets](Self::reset_and_wait) the IC, losing all configuration and
+  /// program memory.
+  ///
+  /// Returns whether `ENGINE_BUSY` is clear afterwards.
+  pub fn recover(
+    &mut self,
+    reset_if_busy: bool,
+  ) -> Result<bool, LinuxI2CError> {
+    debug::scope!(self, "recover(reset_if_busy: {})", reset_if_busy);
+
+    self.set_all_engines_mode(EngineMode::Disabled)?;
+    self.get_status()?;
+
+    let wait = self.wait_while_engine_busy_timeout(
+      timing::ENGINE_BUSY_POLL_INTERVAL,
+      timing::ENGINE_BUSY_TIMEOUT,
+    );
+    match wait {
+      Ok(()) => return Ok(true),
+      Err(LinuxI2CError::Io(error))
+        if error.kind() == std::io::ErrorKind::TimedOut => {}
+      Err(error) => return Err(error),
+    }
+
+    if !reset_if_busy {
+      return Ok(false);
+    }
+
+    debug::text!(self, "ENGINE_BUSY still set; resetting");
+    self
+      .reset_and_wait(timing::STARTUP_POLL_INTERVAL, timing::STARTUP_TIMEOUT)?;
+
+    Ok(!self.get_status()?.engine_busy)
+  }
+
+  /// Select the program memory `page` that subsequent single instruction reads
+  /// and writes apply to (see [`Self::read_program_instruction`] and
+  /// [`Self::write_program_instruction`]). Must be in range \[0:5\].
+  ///
+  /// The selected page is tracked, so `PROG MEM PAGE SEL` is only written when
+  /// the page changes (or is unknown, e.g. after a [reset](Self::reset)).
+  pub fn select_page(&mut self, page: u8) -> Result<(), LinuxI2CError> {
+    validate_page(page)?;
+
+    if self.page == Some(page) {
+      return Ok(());
+    }
+
+    debug::scope!(self, "select_page(page: {})", page);
+
+    self.write_register(Register::PROG_MEM_PAGE_SEL, page)
+  }
+
+  /// Read a single program [`Instruction`] at the specified `index`, from the
+  /// current page (i.e. the page selected via [`Self::select_page`]).
+  pub fn read_program_instruction(
+    &mut self,
+    index: u8,
+  ) -> Result<Instruction, LinuxI2CError> {
+    validate_instruction_index(index)?;
+
+    let register = (Register::PROG_MEM_BASE as u8) + (index * 2);
+    let msb = self.retry(|device| device.smbus_read_byte_data(register))?;
+    let lsb = self.retry(|device| device.smbus_read_byte_data(register + 1))?;
+    debug::text!(
+      self,
+      "[{:02}] << {:02x} & {:02x} {:08b} {:08b} (0x{:02x}{:02x})",
+      index,
+      register,
+      register + 1,
+      msb,
+      lsb,
+      msb,
+      lsb,
+    );
+    Ok(Instruction { msb, lsb })
+  }
+
+  /// Write up to [`INSTRUCTIONS_PER_PAGE`] program [instructions](Instruction)
+  /// to the specified `page`.
+  ///
+  /// Arguments:
+  /// * `page` - The page number to write. Must be in range \[0:5\]
+  /// * `instructions` - List of instructions to write.
+  /// * `at_once` - Whether to write all instructions in a single I2C write
+  ///   or use individual writes (each instruction is 2 bytes, which could
+  ///   result in up to 32 writes).
+  ///
+  /// Writing `at_once` requires `EN_AUTO_INCR` (see
+  /// [`Self::is_auto_increment_enabled`]), failing with
+  /// [`AutoIncrementRequired`] otherwise.
+  pub fn write_program_page(
+    &mut self,
+    page: u8,
+    instructions: &[Instruction],
+    at_once: bool,
+  ) -> Result<(), LinuxI2CError> {
+    validate_page(page)?;
+    validate_per_page_instruction_count(instructions)?;
+
+    debug::scope!(
+      self,
+      "write_program_page(page: {}, [{} instructions])",
+      page,
+      instructions.len()
+    );
+
+    // Select the page...
+    self.select_page(page)?;
+    // ... and write the instructions.
+    if at_once {
+      let bytes: Vec<u8> = instructions
+        .iter()
+        .flat_map(Instruction::as_bytes)
+        .collect();
+      self.write_block(Register::PROG_MEM_BASE as u8, &bytes)?;
+      for (index, instruction) in instructions.iter().enumerate() {
+        debug::text!(
+          self,
+          "[{:02}] >> {:08b} {:08b} (0x{:04x})",
+          index,
+          instruction.msb,
+          instruction.lsb,
+          instruction.as_u16(),
+        );
+      }
+    } else {
+      for (index, instruction) in instructions.iter().enumerate() {
+        self.write_program_instruction(index as u8, instruction)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Write a single program [`Instruction`] at the specified index, to the
+  /// current page (i.e. the page currently selected via
+  /// [`Self::select_page`]).
+  pub fn write_program_instruction(
+    &mut self,
+    index: u8,
+    instr: &Instruction,
+  ) -> Result<(), LinuxI2CError> {
+    validate_instruction_index(index)?;
+
+    let register = (Register::PROG_MEM_BASE as u8) + (index * 2);
+    // See `write_program_instruction_word` for a single u16 write.
+    self.retry(|device| device.smbus_write_byte_data(register, instr.msb))?;
+    self
+      .retry(|device| device.smbus_write_byte_data(register + 1, instr.lsb))?;
+    debug::text!(
+      self,
+      "[{:02}] >> {:02x} & {:02x} {:08b} {:08b} (0x{:02x}{:02x})",
+      index,
+      register,
+      register + 1,
+      instr.msb,
+      instr.lsb,
+      instr.msb,
+      instr.lsb,
+    );
+    Ok(())
+  }
+
+  /// Same as [`Self::write_program_instruction`], but writes the whole
+  /// instruction word in a single I2C block write.
+  ///
+  /// Requires `EN_AUTO_INCR` (see [`Self::set_misc_settings`]), failing with
+  /// [`AutoIncrementRequired`] otherwise.
+  pub fn write_program_instruction_word(
+    &mut self,
+    index: u8,
+    instr: &Instruction,
+  ) -> Result<(), LinuxI2CError> {
+    validate_instruction_index(index)?;
+
+    let register = (Register::PROG_MEM_BASE as u8) + (index * 2);
+    self.write_block(register, &instr.as_bytes())?;
+    debug::text!(
+      self,
+      "[{:02}] >> {:02x} {:08b} {:08b} (0x{:04x})",
+      index,
+      register,
+      instr.msb,
+      instr.lsb,
+      instr.as_u16(),
+    );
+    Ok(())
+  }
+
+  /// Read a program page.
+  ///
+  /// Each page contains up to [`INSTRUCTIONS_PER_PAGE`]
+  /// [instructions](Instruction).
+  ///
+  /// Reading `at_once` (i.e. in a single I2C read) requires `EN_AUTO_INCR`
+  /// (see [`Self::is_auto_increment_enabled`]), failing with
+  /// [`AutoIncrementRequired`] otherwise.
+  pub fn read_program_page(
+    &mut self,
+    page: u8,
+    at_once: bool,
+  ) -> Result<Vec<Instruction>, LinuxI2CError> {
+    validate_page(page)?;
+
+    debug::scope!(self, "read_program_page(page: {})", page);
+
+    self.select_page(page)?;
+    let mut instructions: Vec<Instruction> = vec![];
+    if at_once {
+      let bytes = self
+        .read_block(Register::PROG_MEM_BASE as u8, INSTRUCTIONS_PER_PAGE * 2)?;
+      for (index, word) in bytes.chunks_exact(2).enumerate() {
+        let instruction = Instruction::from([word[0], word[1]]);
+        debug::text!(
+          self,
+          "[{:02}] << {:08b} {:08b} (0x{:04x})",
+          index,
+          instruction.msb,
+          instruction.lsb,
+          instruction.as_u16(),
+        );
+        instructions.push(instruction);
+      }
+    } else {
+      for i in 0..16 {
+        let instruction = self.read_program_instruction(i)?;
+        instructions.push(instruction);
+      }
+    }
+
+    Ok(instructions)
+  }
+
+  /// Read and [decode](Instruction::decode) a program page, e.g. to print a
+  /// listing of the loaded program.
+  ///
+  /// Each decoded instruction is paired with its program memory address
+  /// (i.e. `page * INSTRUCTIONS_PER_PAGE + index`), as used by branch
+  /// instructions and entry points.
+  pub fn read_program_page_decoded(
+    &mut self,
+    page: u8,
+  ) -> Result<Vec<(u8, DecodedInstruction)>, LinuxI2CError> {
+    debug::scope!(self, "read_program_page_decoded(page: {})", page);
+
+    let at_once = self.is_auto_increment_enabled()?;
+    let instructions = self.read_program_page(page, at_once)?;
+    let decoded = instructions
+      .iter()
+      .enumerate()
+      .map(|(index, instruction)| {
+        let address = page * INSTRUCTIONS_PER_PAGE + index as u8;
+        (address, instruction.decode())
+      })
+      .collect();
+
+    Ok(decoded)
+  }
+
+  /// Read the raw bytes of the specified program memory `page`, without
+  /// decoding them into [instructions](Instruction) (e.g. to compare against a
+  /// known-good image).
+  ///
+  /// Like [`Self::read_program_page`], the engines must be in load program
+  /// mode (see [`Self::enter_load_mode`]). Bytes are read in a single I2C
+  /// transfer when `EN_AUTO_INCR` is set, individually otherwise.
+  pub fn read_program_page_bytes(
+    &mut self,
+    page: u8,
+  ) -> Result<[u8; 32], LinuxI2CError> {
+    validate_page(page)?;
+
+    debug::scope!(self, "read_program_page_bytes(page: {})", page);
+
+    self.select_page(page)?;
+    let mut bytes = [0; 32];
+    if self.is_auto_increment_enabled()? {
+      let block =
+        self.read_block(Register::PROG_MEM_BASE as u8, bytes.len() as u8)?;
+      if block.len() != bytes.len() {
+        return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+          "short program memory read ({} bytes); expected {}",
+          block.len(),
+          bytes.len()
+        ))));
+      }
+      bytes.copy_from_slice(&block);
+    } else {
+      for (offset, byte) in bytes.iter_mut().enumerate() {
+        let register = Register::PROG_MEM_BASE as u8 + offset as u8;
+        *byte = self.retry(|device| device.smbus_read_byte_data(register))?;
+      }
+    }
+
+    for (offset, byte) in bytes.iter().enumerate() {
+      debug::byte!(
+        self,
+        *byte,
+        "<< {:02x}",
+        Register::PROG_MEM_BASE as u8 + offset as u8
+      );
+    }
+
+    Ok(bytes)
+  }
+
+  /// Set the starting address for the specified [`Engine`] program instructions.
+  ///
+  /// Defaults:
+  /// - Engine 1: 0
+  /// - Engine 2: 8
+  /// - Engine 3: 16
+  ///
+  /// `entry_point` must be lower than [`MAX_INSTRUCTIONS`].
+  pub fn set_engine_entry_point(
+    &mut self,
+    engine: Engine,
+    entry_point: u8,
+  ) -> Result<(), LinuxI2CError> {
+    validate_entry_point(entry_point)?;
+
+    debug::scope!(
+      self,
+      "set_engine_entry_point(engine: {:?}, entry_point: {})",
+      engine,
+      entry_point
+    );
+
+    self.write_register(Register::program_start_for(engine), entry_point)
+  }
+
+  /// Set the starting addresses for all engines, ordered E1 through E3.
+  ///
+  /// `ENG1 PROG START ADDR` through `ENG3 PROG START ADDR` are contiguous, so
+  /// this is a single I2C write when `EN_AUTO_INCR` is set (see
+  /// [`Self::write_registers`]). Every entry point must be lower than
+  /// [`MAX_INSTRUCTIONS`].
+  pub fn set_engine_entry_points(
+    &mut self,
+    entry_points: [u8; 3],
+  ) -> Result<(), LinuxI2CError> {
+    for entry_point in entry_points {
+      validate_entry_point(entry_point)?;
+    }
+
+    debug::scope!(self, "set_engine_entry_points({:?})", entry_points);
+
+    self.write_contiguous(Register::ENG1_PROG_START_ADDR, &entry_points)
+  }
+
+  /// Read the starting address for the specified [`Engine`] program
+  /// instructions.
+  pub fn get_engine_entry_point(
+    &mut self,
+    engine: Engine,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_engine_entry_point(engine: {:?})", engine);
+
+    self.read_register(Register::program_start_for(engine))
+  }
+
+  /// Read the global variable shared by all programming engines (variable
+  /// [D](Variable::D) in engine programs).
+  pub fn get_global_variable(&mut self) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_global_variable()");
+
+    self.read_register(Register::VARIABLE)
+  }
+
+  /// Set the global variable shared by all programming engines (variable
+  /// [D](Variable::D) in engine programs).
+  ///
+  /// Can be changed while engines are running, e.g. to switch between effects
+  /// without reloading the program.
+  pub fn set_global_variable(
+    &mut self,
+    value: u8,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "set_global_variable({})", value);
+
+    self.write_register(Register::VARIABLE, value)
+  }
+
+  /// Read the value of a programming engine [`Variable`].
+  ///
+  /// Only the following combinations can be read:
+  /// - Engine 1, variables A, B and C (`ENGINE1 VARIABLE A/B/C` registers)
+  /// - Any engine, variable D (the global `VARIABLE` register; see
+  ///   [`Self::get_global_variable`])
+  ///
+  /// Any other combination results in an error.
+  pub fn get_engine_variable(
+    &mut self,
+    engine: Engine,
+    var: Variable,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(
+      self,
+      "get_engine_variable(engine: {:?}, var: {:?})",
+      engine,
+      var
+    );
+
+    let register = Register::variable_for(engine, var).ok_or_else(|| {
+      LinuxI2CError::Io(std::io::Error::other(format!(
+        "variable {:?} of engine {:?} cannot be read",
+        var, engine
+      )))
+    })?;
+
+    self.read_register(register)
+  }
+
+  /// Set program counter value for the specified [`Engine`].
+  ///
   /// NB: Program counter can only be modified if the engines are not running.
   pub fn set_engine_program_counter(
     &mut self,
@@ -545,6 +2136,16 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Read the program counter value for the specified [`Engine`].
+  pub fn get_engine_program_counter(
+    &mut self,
+    engine: Engine,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "get_engine_program_counter(engine: {:?})", engine);
+
+    self.read_register(Register::program_counter_for(engine))
+  }
+
   /// Set [program execution control](EngineExec) for the specified [`Engine`].
   pub fn set_engine_exec(
     &mut self,
@@ -560,7 +2161,7 @@ impl LP55231 {
 
     let current_value = self.read_register(Register::ENABLE_ENGINE_CNTRL1)?;
     let new_value =
-      Mask::exec_for(engine).apply(exec_mode as u8, current_value);
+      Mask::exec_for(engine).apply(exec_mode.bits(), current_value);
     if new_value != current_value {
       self.write_register(Register::ENABLE_ENGINE_CNTRL1, new_value)?;
     }
@@ -568,6 +2169,121 @@ impl LP55231 {
     Ok(())
   }
 
+  /// Read the [program execution control](EngineExec) for the specified
+  /// [`Engine`].
+  pub fn get_engine_exec(
+    &mut self,
+    engine: Engine,
+  ) -> Result<EngineExec, LinuxI2CError> {
+    debug::scope!(self, "get_engine_exec(engine: {:?})", engine);
+
+    let value = self.read_register(Register::ENABLE_ENGINE_CNTRL1)?;
+    Ok(EngineExec::from(Mask::exec_for(engine).value(value)))
+  }
+
+  /// Execute a single instruction on the specified [`Engine`], returning the
+  /// new program counter.
+  ///
+  /// The engine must be in [run mode](EngineMode::RunProgram). Sets execution
+  /// control to [`EngineExec::Step`] and polls at intervals of specified
+  /// duration until the IC sets it back to [`EngineExec::Hold`], which happens
+  /// once the instruction completes (i.e. ramps and waits block the step).
+  ///
+  /// Returns an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut) if
+  /// the instruction is still executing once `timeout` elapses.
+  pub fn step_engine(
+    &mut self,
+    engine: Engine,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<u8, LinuxI2CError> {
+    debug::scope!(self, "step_engine(engine: {:?})", engine);
+
+    // The IC clears the step bit on its own, so read-after-write verification
+    // would race against it.
+    let verify_writes = std::mem::replace(&mut self.verify_writes, false);
+    let result = self.set_engine_exec(engine, EngineExec::Step);
+    self.verify_writes = verify_writes;
+    result?;
+
+    let deadline = Instant::now() + timeout;
+    while self.get_engine_exec(engine)? != EngineExec::Hold {
+      if Instant::now() >= deadline {
+        return Err(LinuxI2CError::Io(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!("engine {:?} still stepping after {:?}", engine, timeout),
+        )));
+      }
+      sleep(poll_interval);
+    }
+
+    self.get_engine_program_counter(engine)
+  }
+
+  /// Run the program of the specified [`Engine`], optionally from a new
+  /// `entry_point`, and wait for it to finish (e.g. to re-arm a one-shot
+  /// effect).
+  ///
+  /// Starts the engine like [`Self::start_engine`] (i.e. in
+  /// [`EngineExec::Free`]), then polls at intervals of specified duration
+  /// until either the engine raises an interrupt or the IC sets its execution
+  /// control back to [`EngineExec::Hold`], both of which happen once the
+  /// program reaches an [`Instruction::end`]. Programs that never end (e.g.
+  /// looping forever) time out.
+  ///
+  /// NB: Despite the name, [`EngineExec::ExecuteOnce`] executes a single
+  /// instruction rather than the whole program, so it isn't used here.
+  ///
+  /// NB: Polling reads `STATUS_INTERRUPT`, which clears pending interrupts
+  /// for *all* engines.
+  ///
+  /// Returns an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut) if
+  /// the engine is still running once `timeout` elapses.
+  pub fn run_once(
+    &mut self,
+    engine: Engine,
+    entry_point: Option<u8>,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(
+      self,
+      "run_once(engine: {:?}, entry_point: {:?})",
+      engine,
+      entry_point
+    );
+
+    self.set_engine_exec(engine, EngineExec::Hold)?;
+    if let Some(entry_point) = entry_point {
+      self.set_engine_entry_point(engine, entry_point)?;
+      self.set_engine_program_counter(engine, entry_point)?;
+    }
+    self.set_engine_mode(engine, EngineMode::RunProgram)?;
+
+    // The IC returns the engine to hold on its own once the program ends,
+    // possibly before a read-after-write verification.
+    let verify_writes = std::mem::replace(&mut self.verify_writes, false);
+    let result = self.set_engine_exec(engine, EngineExec::Free);
+    self.verify_writes = verify_writes;
+    result?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+      if self.engine_interrupted(engine)?
+        || self.get_engine_exec(engine)? == EngineExec::Hold
+      {
+        return Ok(());
+      }
+      if Instant::now() >= deadline {
+        return Err(LinuxI2CError::Io(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!("engine {:?} still running after {:?}", engine, timeout),
+        )));
+      }
+      sleep(poll_interval);
+    }
+  }
+
   /// Convenience alias for [`Self::set_engine_modes`]
   /// that applies the same mode to all engines.
   pub fn set_all_engines_mode(
@@ -592,9 +2308,9 @@ impl LP55231 {
       engine3
     );
 
-    let e1_bits = Mask::ENGINE1_MODE.with(engine1 as u8);
-    let e2_bits = Mask::ENGINE2_MODE.with(engine2 as u8);
-    let e3_bits = Mask::ENGINE2_MODE.with(engine3 as u8);
+    let e1_bits = Mask::ENGINE1_MODE.with(engine1.bits());
+    let e2_bits = Mask::ENGINE2_MODE.with(engine2.bits());
+    let e3_bits = Mask::ENGINE3_MODE.with(engine3.bits());
 
     let value = e1_bits | e2_bits | e3_bits;
 
@@ -609,13 +2325,205 @@ impl LP55231 {
   ) -> Result<(), LinuxI2CError> {
     debug::scope!(self, "set_engine_mode({:?}, {:?})", engine, op_mode);
 
-    let current_value = self.read_register(Register::ENGINE_CNTRL_2)?;
-    let new_value = Mask::mode_for(engine).apply(op_mode as u8, current_value);
-    if new_value != current_value {
-      self.write_register(Register::ENGINE_CNTRL_2, new_value)?;
+    let current_value = self.read_register(Register::ENGINE_CNTRL_2)?;
+    let new_value = Mask::mode_for(engine).apply(op_mode.bits(), current_value);
+    if new_value != current_value {
+      self.write_register(Register::ENGINE_CNTRL_2, new_value)?;
+    }
+
+    Ok(())
+  }
+
+  /// Read the [`EngineMode`] for the specified [`Engine`].
+  pub fn get_engine_mode(
+    &mut self,
+    engine: Engine,
+  ) -> Result<EngineMode, LinuxI2CError> {
+    debug::scope!(self, "get_engine_mode({:?})", engine);
+
+    let value = self.read_register(Register::ENGINE_CNTRL_2)?;
+    Ok(EngineMode::from(Mask::mode_for(engine).value(value)))
+  }
+
+  /// Read the raw values of the `ENABLE / ENGINE CNTRL1` and `ENGINE CNTRL2`
+  /// registers, in that order.
+  ///
+  /// Useful to cross-check the execution control and mode of all engines at
+  /// once against the spec; see [`Self::get_engine_control_settings`] for the
+  /// decoded values.
+  pub fn get_engine_control(&mut self) -> Result<(u8, u8), LinuxI2CError> {
+    debug::scope!(self, "get_engine_control()");
+
+    let cntrl1 = self.read_register(Register::ENABLE_ENGINE_CNTRL1)?;
+    let cntrl2 = self.read_register(Register::ENGINE_CNTRL_2)?;
+
+    Ok((cntrl1, cntrl2))
+  }
+
+  /// Read the [`EngineExec`] and [`EngineMode`] of every engine.
+  pub fn get_engine_control_settings(
+    &mut self,
+  ) -> Result<EngineControl, LinuxI2CError> {
+    debug::scope!(self, "get_engine_control_settings()");
+
+    let (cntrl1, cntrl2) = self.get_engine_control()?;
+    let exec = |engine| EngineExec::from(Mask::exec_for(engine).value(cntrl1));
+    let mode = |engine| EngineMode::from(Mask::mode_for(engine).value(cntrl2));
+    let control = EngineControl {
+      engine1_exec: exec(Engine::E1),
+      engine2_exec: exec(Engine::E2),
+      engine3_exec: exec(Engine::E3),
+      engine1_mode: mode(Engine::E1),
+      engine2_mode: mode(Engine::E2),
+      engine3_mode: mode(Engine::E3),
+    };
+
+    Ok(control)
+  }
+
+  /// Capture a snapshot of the whole chip state.
+  ///
+  /// Composes the individual read methods to read every channel, engine and
+  /// global setting, including the raw `STATUS_INTERRUPT` byte.
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears any pending engine interrupts.
+  pub fn get_state(&mut self) -> Result<ChipState, LinuxI2CError> {
+    debug::scope!(self, "get_state()");
+
+    let enabled = self.is_enabled()?;
+    let misc = self.get_misc_settings()?;
+
+    let on_off = self.get_channel_on_off()?;
+    let ratiometric = self.get_ratiometric()?;
+    let mut channels = Vec::with_capacity(9);
+    for channel in Channel::all() {
+      channels.push(ChannelState {
+        channel,
+        pwm: self.get_channel_pwm(channel)?,
+        current: self.get_channel_current(channel)?,
+        enabled: on_off[channel as usize],
+        log_brightness: self.get_log_brightness(channel)?,
+        ratiometric_dimming: ratiometric[channel as usize],
+        fader_assignment: self.get_fader_assignment(channel)?,
+      });
+    }
+
+    let mut engines = Vec::with_capacity(3);
+    for engine in Engine::all() {
+      engines.push(EngineState {
+        engine,
+        mode: self.get_engine_mode(engine)?,
+        exec: self.get_engine_exec(engine)?,
+        program_counter: self.get_engine_program_counter(engine)?,
+        entry_point: self.get_engine_entry_point(engine)?,
+      });
+    }
+
+    let status = self.read_register(Register::STATUS_INTERRUPT)?;
+
+    Ok(ChipState {
+      enabled,
+      misc,
+      channels,
+      engines,
+      status,
+    })
+  }
+
+  /// Reset the IC and re-apply a snapshot captured with [`Self::get_state`],
+  /// e.g. to recover the prior configuration after a brownout.
+  ///
+  /// Program memory isn't part of the snapshot; when `program` is provided,
+  /// it's [loaded](Self::load_program) before restoring engine state.
+  ///
+  /// Restores, in order:
+  /// 1. [Misc] settings and `CHIP_EN`
+  /// 2. Per-channel PWM, current, enabled state, logarithmic brightness,
+  ///    ratiometric dimming, and fader assignment
+  /// 3. The program, if any
+  /// 4. Per-engine entry point, program counter, mode, and execution control
+  ///
+  /// The `status` of the snapshot is not restored (interrupts can't be
+  /// raised from the host).
+  pub fn restore_state(
+    &mut self,
+    state: &ChipState,
+    program: Option<&[Instruction]>,
+  ) -> Result<(), LinuxI2CError> {
+    debug::scope!(self, "restore_state({:?})", state);
+
+    self
+      .reset_and_wait(timing::STARTUP_POLL_INTERVAL, timing::STARTUP_TIMEOUT)?;
+    self.set_misc_settings(state.misc)?;
+    if state.enabled {
+      self.set_enabled(true)?;
+      sleep(timing::CHIP_EN_STARTUP);
+    }
+
+    let mut pwm = [0; 9];
+    let mut current = [0; 9];
+    let mut enabled_channels = vec![];
+    for channel_state in &state.channels {
+      let index = channel_state.channel as usize;
+      pwm[index] = channel_state.pwm;
+      current[index] = channel_state.current;
+      if channel_state.enabled {
+        enabled_channels.push(channel_state.channel);
+      }
+    }
+    self.set_all_pwm(pwm)?;
+    self.set_all_current(current)?;
+    self.set_enabled_channels(&enabled_channels)?;
+    for channel_state in &state.channels {
+      let channel = channel_state.channel;
+      self.set_log_brightness(channel, channel_state.log_brightness)?;
+      self
+        .set_ratiometric_dimming(channel, channel_state.ratiometric_dimming)?;
+      self.assign_to_fader(channel, channel_state.fader_assignment)?;
+    }
+
+    if let Some(program) = program {
+      self.load_program(program)?;
+    }
+
+    for engine_state in &state.engines {
+      let engine = engine_state.engine;
+      self.set_engine_entry_point(engine, engine_state.entry_point)?;
+      self.set_engine_program_counter(engine, engine_state.program_counter)?;
+    }
+
+    // Like `run_program`, modes are set before execution control so that no
+    // engine starts executing before the others are in their modes.
+    let mut modes = [EngineMode::Disabled; 3];
+    for engine_state in &state.engines {
+      modes[engine_state.engine as usize] = engine_state.mode;
+    }
+    let [engine1, engine2, engine3] = modes;
+    self.set_engine_modes(engine1, engine2, engine3)?;
+    for engine_state in &state.engines {
+      self.set_engine_exec(engine_state.engine, engine_state.exec)?;
+    }
+
+    Ok(())
+  }
+
+  /// Read every [readable](Register::is_readable) [`Register`], i.e. every
+  /// control register (program memory isn't included).
+  ///
+  /// Returns register and value pairs, sorted by address.
+  ///
+  /// NB: Reading `STATUS_INTERRUPT` clears any pending engine interrupts.
+  pub fn dump_registers(
+    &mut self,
+  ) -> Result<Vec<(Register, u8)>, LinuxI2CError> {
+    debug::scope!(self, "dump_registers()");
+
+    let mut values = Vec::with_capacity(Register::ALL.len());
+    for register in Register::ALL.iter().filter(|r| r.is_readable()) {
+      values.push((*register, self.read_register(*register)?));
     }
 
-    Ok(())
+    Ok(values)
   }
 
   /// Read a byte from the specified [`Register`].
@@ -623,8 +2531,10 @@ impl LP55231 {
     &mut self,
     register: Register,
   ) -> Result<u8, LinuxI2CError> {
-    let value = self.device.smbus_read_byte_data(register as u8)?;
+    let value =
+      self.retry(|device| device.smbus_read_byte_data(register as u8))?;
     debug::byte!(self, value, "<< {:02x} {:?}", register as u8, register);
+    self.track_register(register, value);
     Ok(value)
   }
 
@@ -635,17 +2545,124 @@ impl LP55231 {
     value: u8,
   ) -> Result<(), LinuxI2CError> {
     debug::byte!(self, value, ">> {:02x} {:?}", register as u8, register);
-    self.device.smbus_write_byte_data(register as u8, value)?;
+    self.retry(|device| device.smbus_write_byte_data(register as u8, value))?;
+    self.track_register(register, value);
 
-    if self.verify_writes {
+    if self.verify_writes && register.is_verifiable() {
       let post_write_value =
-        self.device.smbus_read_byte_data(register as u8)?;
+        self.retry(|device| device.smbus_read_byte_data(register as u8))?;
       if post_write_value != value {
-        return Err(LinuxI2CError::Io(std::io::Error::new(
-          std::io::ErrorKind::Other,
+        return Err(LinuxI2CError::Io(std::io::Error::other(
+          format!(
+            "write to register {} failed; read-after-write expecting {:08b} but got {:08b}",
+            register, value, post_write_value,
+          ),
+        )));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Read a byte from an arbitrary `address`, e.g. to experiment with
+  /// undocumented registers during bring-up.
+  ///
+  /// Unlike [`Self::read_register`], any address is accepted, including the
+  /// [reserved ranges](Register::is_reserved) of the register map, whose
+  /// contents are undefined; reading those prints a warning when
+  /// [debug output](Self::debug_enabled) is enabled.
+  pub fn read_raw(&mut self, address: u8) -> Result<u8, LinuxI2CError> {
+    if Register::is_reserved(address) {
+      debug::text!(self, "warning: reading reserved address {:02x}", address);
+    }
+
+    let value = self.retry(|device| device.smbus_read_byte_data(address))?;
+    debug::byte!(self, value, "<< {:02x}", address);
+    if let Some(register) = Register::from_address(address) {
+      self.track_register(register, value);
+    }
+    Ok(value)
+  }
+
+  /// Write a byte to an arbitrary `address`, e.g. to experiment with
+  /// undocumented registers during bring-up.
+  ///
+  /// Unlike [`Self::write_register`], any address is accepted, including the
+  /// [reserved ranges](Register::is_reserved) of the register map, where
+  /// writes may have undocumented effects; writing those prints a warning when
+  /// [debug output](Self::debug_enabled) is enabled. Writes aren't
+  /// [verified](Self::verify_writes).
+  pub fn write_raw(
+    &mut self,
+    address: u8,
+    value: u8,
+  ) -> Result<(), LinuxI2CError> {
+    if Register::is_reserved(address) {
+      debug::text!(self, "warning: writing reserved address {:02x}", address);
+    }
+
+    debug::byte!(self, value, ">> {:02x}", address);
+    self.retry(|device| device.smbus_write_byte_data(address, value))?;
+    if let Some(register) = Register::from_address(address) {
+      self.track_register(register, value);
+    }
+    Ok(())
+  }
+
+  /// Write consecutive bytes, starting at the specified [`Register`], in a
+  /// single I2C block write.
+  ///
+  /// Requires `EN_AUTO_INCR` (see [`Self::set_auto_increment`]), failing with
+  /// [`AutoIncrementRequired`] before writing anything otherwise.
+  pub fn write_registers(
+    &mut self,
+    start: Register,
+    values: &[u8],
+  ) -> Result<(), LinuxI2CError> {
+    // Every address in the block must map to a register (i.e. the block can't
+    // span reserved addresses).
+    let mut registers = Vec::with_capacity(values.len());
+    for offset in 0..values.len() {
+      let address = start as usize + offset;
+      let register =
+        u8::try_from(address).ok().and_then(Register::from_address);
+      match register {
+        Some(register) => registers.push(register),
+        None => {
+          return Err(LinuxI2CError::Io(std::io::Error::other(format!(
+            "invalid block write of {} bytes at {}; 0x{:02x} is not a register",
+            values.len(),
+            start,
+            address,
+          ))))
+        }
+      }
+    }
+
+    self.require_auto_increment()?;
+
+    for (register, value) in registers.iter().zip(values) {
+      debug::byte!(self, *value, ">> {:02x} {:?}", *register as u8, register);
+    }
+    self.write_block(start as u8, values)?;
+    for (register, value) in registers.iter().zip(values) {
+      self.track_register(*register, *value);
+    }
+
+    if self.verify_writes {
+      let post_write_values =
+        self.read_block(start as u8, values.len() as u8)?;
+      // Registers that can't be verified are excluded from the comparison.
+      let mismatch = registers.iter().zip(values).zip(&post_write_values).any(
+        |((register, value), post_write_value)| {
+          register.is_verifiable() && value != post_write_value
+        },
+      );
+      if mismatch || post_write_values.len() != values.len() {
+        return Err(LinuxI2CError::Io(std::io::Error::other(
           format!(
-            "write to register {:02x} {:?} failed; read-after-write expecting {:08b} but got {:08b}",
-            register as u8, register, value, post_write_value,
+            "block write to register {} failed; read-after-write expecting {:02x?} but got {:02x?}",
+            start, values, post_write_values,
           ),
         )));
       }
@@ -654,6 +2671,115 @@ impl LP55231 {
     Ok(())
   }
 
+  // Write consecutive registers with `write_registers` if auto-increment is
+  // enabled, or one by one otherwise.
+  fn write_contiguous(
+    &mut self,
+    start: Register,
+    values: &[u8],
+  ) -> Result<(), LinuxI2CError> {
+    if self.is_auto_increment_enabled()? {
+      return self.write_registers(start, values);
+    }
+
+    for (offset, value) in values.iter().enumerate() {
+      let address = start as u8 + offset as u8;
+      let register = Register::from_address(address).ok_or_else(|| {
+        LinuxI2CError::Io(std::io::Error::other(format!(
+          "invalid write at {}; 0x{:02x} is not a register",
+          start, address
+        )))
+      })?;
+      self.write_register(register, *value)?;
+    }
+    Ok(())
+  }
+
+  // Write a block of bytes in a single I2C transfer, starting at `address`.
+  fn write_block(
+    &mut self,
+    address: u8,
+    values: &[u8],
+  ) -> Result<(), LinuxI2CError> {
+    self.require_auto_increment()?;
+    self.retry(|device| device.smbus_write_i2c_block_data(address, values))
+  }
+
+  // Read a block of `len` bytes in a single I2C transfer, starting at
+  // `address`.
+  fn read_block(
+    &mut self,
+    address: u8,
+    len: u8,
+  ) -> Result<Vec<u8>, LinuxI2CError> {
+    self.require_auto_increment()?;
+    self.retry(|device| device.smbus_read_i2c_block_data(address, len))
+  }
+
+  // Fail with `AutoIncrementRequired` unless `EN_AUTO_INCR` is set, since a
+  // block transfer would otherwise access the same register over and over.
+  fn require_auto_increment(&mut self) -> Result<(), LinuxI2CError> {
+    if self.is_auto_increment_enabled()? {
+      return Ok(());
+    }
+
+    Err(LinuxI2CError::Io(std::io::Error::other(
+      AutoIncrementRequired,
+    )))
+  }
+
+  // Apply `current_limit` to a channel current value.
+  fn limit_current(&self, current: u8) -> Result<u8, LinuxI2CError> {
+    match self.current_limit {
+      Some(CurrentLimit::Clamp(max)) => Ok(current.min(max)),
+      Some(CurrentLimit::Reject(max)) if current > max => {
+        Err(LinuxI2CError::Io(std::io::Error::other(format!(
+          "current ({}) exceeds limit; must be in range [0:{}]",
+          current, max
+        ))))
+      }
+      _ => Ok(current),
+    }
+  }
+
+  // Keep track of state derived from register values read or written.
+  fn track_register(&mut self, register: Register, value: u8) {
+    match register {
+      Register::MISC => {
+        self.auto_increment = Some(Mask::EN_AUTO_INCR.is_set(value))
+      }
+      Register::PROG_MEM_PAGE_SEL => {
+        self.page = Some(Mask::PAGE_SEL.value(value))
+      }
+      // Reset restores MISC and PROG MEM PAGE SEL to their default values;
+      // read them again on next use.
+      Register::RESET => {
+        self.auto_increment = None;
+        self.page = None;
+      }
+      _ => {}
+    }
+  }
+
+  // Run an I2C transfer, retrying transient errors as configured via
+  // `max_retries` and `retry_backoff`.
+  fn retry<T>(
+    &mut self,
+    mut transfer: impl FnMut(&mut D) -> Result<T, LinuxI2CError>,
+  ) -> Result<T, LinuxI2CError> {
+    let mut attempt = 0;
+    loop {
+      match transfer(&mut self.device) {
+        Err(error) if attempt < self.max_retries && is_transient(&error) => {
+          attempt += 1;
+          debug::text!(self, "retry {} after error: {:?}", attempt, error);
+          sleep(self.retry_backoff * attempt as u32);
+        }
+        result => return result,
+      }
+    }
+  }
+
   /// Wait for the `ENGINE_BUSY` bit to clear, polling at intervals of
   /// specified duration.
   ///
@@ -670,6 +2796,149 @@ impl LP55231 {
       sleep(poll_interval);
     }
   }
+
+  /// Wait for the `ENGINE_BUSY` bit to clear, polling at intervals of
+  /// specified duration, for up to `timeout`.
+  ///
+  /// Returns immediately if busy bit is not set, or an error of kind
+  /// [`TimedOut`](std::io::ErrorKind::TimedOut) if the bit is still set once
+  /// `timeout` elapses.
+  pub fn wait_while_engine_busy_timeout(
+    &mut self,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<(), LinuxI2CError> {
+    self.wait_while_status_set(
+      Mask::ENGINE_BUSY,
+      "ENGINE_BUSY",
+      poll_interval,
+      timeout,
+    )
+  }
+
+  /// Wait for the `STARTUP_BUSY` bit to clear, polling at intervals of
+  /// specified duration, for up to `timeout`.
+  ///
+  /// The bit is set while the IC initializes after a [reset](Self::reset);
+  /// writes issued before it clears may be lost.
+  ///
+  /// Returns immediately if busy bit is not set, or an error of kind
+  /// [`TimedOut`](std::io::ErrorKind::TimedOut) if the bit is still set once
+  /// `timeout` elapses.
+  pub fn wait_while_startup_busy(
+    &mut self,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<(), LinuxI2CError> {
+    self.wait_while_status_set(
+      Mask::STARTUP_BUSY,
+      "STARTUP_BUSY",
+      poll_interval,
+      timeout,
+    )
+  }
+
+  fn wait_while_status_set(
+    &mut self,
+    mask: Mask,
+    name: &str,
+    poll_interval: Duration,
+    timeout: Duration,
+  ) -> Result<(), LinuxI2CError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+      let value = self.read_register(Register::STATUS_INTERRUPT)?;
+      if !mask.is_set(value) {
+        return Ok(());
+      }
+      if Instant::now() >= deadline {
+        return Err(LinuxI2CError::Io(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!("{} still set after {:?}", name, timeout),
+        )));
+      }
+      sleep(poll_interval);
+    }
+  }
+}
+
+impl<D> Drop for LP55231<D>
+where
+  D: I2CDevice<Error = LinuxI2CError>,
+{
+  fn drop(&mut self) {
+    if !self.disable_on_drop {
+      return;
+    }
+
+    if let Err(error) = self.stop(true) {
+      debug::text!(self, "failed to disable on drop: {:?}", error);
+    }
+  }
+}
+
+// Whether an I2C error is likely to succeed on retry (e.g. a NAK or lost
+// arbitration), based on the Linux errno. ENXIO isn't retried: nothing
+// acknowledged the address, i.e. the device is missing rather than glitching.
+fn is_transient(error: &LinuxI2CError) -> bool {
+  const EIO: i32 = 5;
+  const EAGAIN: i32 = 11;
+  const ETIMEDOUT: i32 = 110;
+  const EREMOTEIO: i32 = 121;
+
+  let errno = match error {
+    LinuxI2CError::Nix(errno) => Some(*errno as i32),
+    LinuxI2CError::Io(error) => error.raw_os_error(),
+  };
+  matches!(errno, Some(EIO | EAGAIN | ETIMEDOUT | EREMOTEIO))
+}
+
+// Value `step` out of `steps` on the straight line from `from` to `to`.
+fn interpolate(from: u8, to: u8, step: u32, steps: u32) -> u8 {
+  let delta = (to as f32 - from as f32) * step as f32 / steps as f32;
+  (from as f32 + delta).round() as u8
+}
+
+// Per-channel flags, ordered D1 through D9, packed into the MSB and LSB of a
+// register pair; D9 is on the MSB register, D1 through D8 on the LSB register.
+fn split_channel_bits(
+  flags: [bool; 9],
+  mask_for: fn(Channel) -> Mask,
+) -> (u8, u8) {
+  let (mut msb, mut lsb) = (0, 0);
+  for (channel, set) in Channel::all().into_iter().zip(flags) {
+    let bits = mask_for(channel).with(set as u8);
+    if channel == Channel::D9 {
+      msb |= bits;
+    } else {
+      lsb |= bits;
+    }
+  }
+  (msb, lsb)
+}
+
+// Inverse of `split_channel_bits`.
+fn join_channel_bits(
+  msb: u8,
+  lsb: u8,
+  mask_for: fn(Channel) -> Mask,
+) -> [bool; 9] {
+  Channel::all().map(|channel| {
+    let byte = if channel == Channel::D9 { msb } else { lsb };
+    mask_for(channel).is_set(byte)
+  })
+}
+
+// `value` scaled by `intensity / 255`, rounded to the nearest integer.
+fn scale_intensity(value: u8, intensity: u8) -> u8 {
+  ((value as u16 * intensity as u16 + 127) / 255) as u8
+}
+
+fn sleep_until(deadline: Instant) {
+  let now = Instant::now();
+  if now < deadline {
+    sleep(deadline - now);
+  }
 }
 
 fn validate_page(page: u8) -> Result<(), LinuxI2CError> {
@@ -677,10 +2946,10 @@ fn validate_page(page: u8) -> Result<(), LinuxI2CError> {
     return Ok(());
   }
 
-  Err(LinuxI2CError::Io(std::io::Error::new(
-    std::io::ErrorKind::Other,
-    format!("invalid page ({}); must be in range [0:5]", page),
-  )))
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "invalid page ({}); must be in range [0:5]",
+    page
+  ))))
 }
 
 fn validate_instruction_index(index: u8) -> Result<(), LinuxI2CError> {
@@ -688,13 +2957,10 @@ fn validate_instruction_index(index: u8) -> Result<(), LinuxI2CError> {
     return Ok(());
   }
 
-  Err(LinuxI2CError::Io(std::io::Error::new(
-    std::io::ErrorKind::Other,
-    format!(
-      "invalid instruction index ({}); must be in range [0:15]",
-      index
-    ),
-  )))
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "invalid instruction index ({}); must be in range [0:15]",
+    index
+  ))))
 }
 
 fn validate_per_page_instruction_count(
@@ -704,14 +2970,11 @@ fn validate_per_page_instruction_count(
     return Ok(());
   }
 
-  Err(LinuxI2CError::Io(std::io::Error::new(
-    std::io::ErrorKind::Other,
-    format!(
-      "too many instructions for a page ({}); limit is {}",
-      instructions.len(),
-      INSTRUCTIONS_PER_PAGE
-    ),
-  )))
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "too many instructions for a page ({}); limit is {}",
+    instructions.len(),
+    INSTRUCTIONS_PER_PAGE
+  ))))
 }
 
 fn validate_total_instruction_count(
@@ -721,14 +2984,11 @@ fn validate_total_instruction_count(
     return Ok(());
   }
 
-  Err(LinuxI2CError::Io(std::io::Error::new(
-    std::io::ErrorKind::Other,
-    format!(
-      "too many instructions ({}); limit is {}",
-      instructions.len(),
-      MAX_INSTRUCTIONS
-    ),
-  )))
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "too many instructions ({}); limit is {}",
+    instructions.len(),
+    MAX_INSTRUCTIONS
+  ))))
 }
 
 fn validate_program_counter(counter: u8) -> Result<(), LinuxI2CError> {
@@ -736,11 +2996,602 @@ fn validate_program_counter(counter: u8) -> Result<(), LinuxI2CError> {
     return Ok(());
   }
 
-  Err(LinuxI2CError::Io(std::io::Error::new(
-    std::io::ErrorKind::Other,
-    format!(
-      "invalid program counter ({}); must be in range [0:{}]",
-      counter, MAX_INSTRUCTIONS
-    ),
-  )))
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "invalid program counter ({}); must be in range [0:{}]",
+    counter, MAX_INSTRUCTIONS
+  ))))
+}
+
+fn validate_address(address: u8) -> Result<(), LinuxI2CError> {
+  if address < MAX_INSTRUCTIONS {
+    return Ok(());
+  }
+
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "invalid instruction address ({}); must be in range [0:{}]",
+    address,
+    MAX_INSTRUCTIONS - 1
+  ))))
+}
+
+fn validate_entry_point(entry_point: u8) -> Result<(), LinuxI2CError> {
+  if entry_point < MAX_INSTRUCTIONS {
+    return Ok(());
+  }
+
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "invalid entry point ({}); must be in range [0:{}]",
+    entry_point,
+    MAX_INSTRUCTIONS - 1
+  ))))
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use test_util::MockI2c;
+
+  const EIO: i32 = 5;
+  const ENXIO: i32 = 6;
+
+  fn program(len: u16) -> Vec<Instruction> {
+    (0..len)
+      .map(|i| Instruction::set_pwm(i as u8 + 1))
+      .collect()
+  }
+
+  fn page_bytes(instructions: &[Instruction]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = instructions
+      .iter()
+      .flat_map(Instruction::as_bytes)
+      .collect();
+    bytes.resize(INSTRUCTIONS_PER_PAGE as usize * 2, 0);
+    bytes
+  }
+
+  fn is_auto_increment_required(error: &LinuxI2CError) -> bool {
+    match error {
+      LinuxI2CError::Io(error) => error
+        .get_ref()
+        .is_some_and(|error| error.is::<AutoIncrementRequired>()),
+      LinuxI2CError::Nix(_) => false,
+    }
+  }
+
+  #[test]
+  fn set_enabled_preserves_engine_exec_bits() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::ENABLE_ENGINE_CNTRL1, 0b0010_0011);
+    let mut ic = LP55231::new(device);
+
+    ic.set_enabled(true).unwrap();
+    ic.device()
+      .expect_write(Register::ENABLE_ENGINE_CNTRL1, 0b0110_0011);
+
+    ic.set_enabled(false).unwrap();
+    ic.device()
+      .expect_write(Register::ENABLE_ENGINE_CNTRL1, 0b0010_0011);
+  }
+
+  #[test]
+  fn set_channel_enabled_changes_only_its_bit() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::OUTPUT_ON_OFF_CONTROL_LSB, 0b1111_1111);
+    let mut ic = LP55231::new(device);
+
+    ic.set_channel_enabled(Channel::D3, false).unwrap();
+    ic.device()
+      .expect_write(Register::OUTPUT_ON_OFF_CONTROL_LSB, 0b1111_1011);
+
+    ic.device_mut().clear_writes();
+    ic.set_channel_enabled(Channel::D9, true).unwrap();
+    ic.device()
+      .expect_write(Register::OUTPUT_ON_OFF_CONTROL_MSB, 1);
+    ic.device()
+      .expect_no_write(Register::OUTPUT_ON_OFF_CONTROL_LSB);
+  }
+
+  #[test]
+  fn set_channel_enabled_skips_unchanged_writes() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::OUTPUT_ON_OFF_CONTROL_LSB, 0b0000_0001);
+    let mut ic = LP55231::new(device);
+
+    ic.set_channel_enabled(Channel::D1, true).unwrap();
+    ic.device()
+      .expect_no_write(Register::OUTPUT_ON_OFF_CONTROL_LSB);
+  }
+
+  #[test]
+  fn load_program_writes_pages_and_disables_engines() {
+    for auto_increment in [false, true] {
+      let mut ic = LP55231::new(MockI2c::new());
+      ic.set_auto_increment(auto_increment).unwrap();
+      let instructions = program(20);
+
+      ic.load_program(&instructions).unwrap();
+
+      let device = ic.device();
+      assert_eq!(device.program_page(0), page_bytes(&instructions[..16]));
+      assert_eq!(device.program_page(1), page_bytes(&instructions[16..]));
+      device.expect_write(Register::ENGINE_CNTRL_2, 0b0001_0101);
+      assert_eq!(device.register(Register::ENGINE_CNTRL_2), 0);
+    }
+  }
+
+  #[test]
+  fn block_transfers_require_auto_increment() {
+    let mut ic = LP55231::new(MockI2c::new());
+    ic.set_auto_increment(false).unwrap();
+    ic.device_mut().clear_writes();
+
+    let error = ic
+      .write_registers(Register::D1_PWM, &[1, 2, 3])
+      .unwrap_err();
+    assert!(is_auto_increment_required(&error));
+    ic.device().expect_no_write(Register::D1_PWM);
+
+    let error = ic.read_program_page(0, true).unwrap_err();
+    assert!(is_auto_increment_required(&error));
+
+    let error = ic
+      .write_program_instruction_word(0, &Instruction::END)
+      .unwrap_err();
+    assert!(is_auto_increment_required(&error));
+  }
+
+  #[test]
+  fn contiguous_writes_fall_back_without_auto_increment() {
+    let mut ic = LP55231::new(MockI2c::new());
+    ic.set_auto_increment(false).unwrap();
+
+    ic.set_all_pwm([1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+
+    for channel in Channel::all() {
+      let pwm = channel.as_index() as u8 + 1;
+      ic.device().expect_write(Register::pwm_for(channel), pwm);
+    }
+  }
+
+  #[test]
+  fn disabled_debug_output_does_not_lock_debug_depth() {
+    let mut ic = LP55231::new(MockI2c::new());
+    // Poison the lock, so that any attempt to take it panics.
+    let depth = ic.debug_depth.clone();
+    let _ = std::thread::spawn(move || {
+      let _guard = depth.lock().unwrap();
+      panic!("poisoning debug_depth");
+    })
+    .join();
+    assert!(ic.debug_depth.is_poisoned());
+
+    ic.is_enabled().unwrap();
+    ic.read_register(Register::MISC).unwrap();
+    ic.write_register(Register::D1_PWM, 1).unwrap();
+
+    ic.debug_enabled = true;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      ic.is_enabled().unwrap();
+    }));
+    assert!(result.is_err());
+  }
+
+  #[test]
+  fn wait_for_engine_interrupt_checks_only_its_engine() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::STATUS_INTERRUPT, Mask::ENG2_INT.bits());
+    let mut ic = LP55231::new(device);
+
+    ic.wait_for_engine_interrupt(Engine::E2, Duration::ZERO, Duration::ZERO)
+      .unwrap();
+
+    let error = ic
+      .wait_for_engine_interrupt(Engine::E1, Duration::ZERO, Duration::ZERO)
+      .unwrap_err();
+    match error {
+      LinuxI2CError::Io(error) => {
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut)
+      }
+      LinuxI2CError::Nix(error) => panic!("unexpected error {:?}", error),
+    }
+  }
+
+  // INT pin asserted (low) from the specified sample onwards.
+  struct IntPin {
+    samples: std::cell::Cell<usize>,
+    low_from: usize,
+  }
+
+  impl InputPin for IntPin {
+    type Error = ();
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+      self.is_low().map(|low| !low)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+      let sample = self.samples.get();
+      self.samples.set(sample + 1);
+      Ok(sample >= self.low_from)
+    }
+  }
+
+  #[test]
+  fn wait_for_interrupt_reads_and_clears_status_once_int_is_low() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::STATUS_INTERRUPT, Mask::ENG1_INT.bits());
+    let mut ic = LP55231::new(device);
+    let mut pin = IntPin {
+      samples: std::cell::Cell::new(0),
+      low_from: 3,
+    };
+
+    let status = ic
+      .wait_for_interrupt(&mut pin, Duration::ZERO, Duration::from_secs(1))
+      .unwrap();
+
+    assert_eq!(pin.samples.get(), 4);
+    assert!(status.engine1_interrupt);
+    assert_eq!(ic.device().register(Register::STATUS_INTERRUPT), 0);
+  }
+
+  #[test]
+  fn wait_for_interrupt_times_out_while_int_is_high() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::STATUS_INTERRUPT, Mask::ENG1_INT.bits());
+    let mut ic = LP55231::new(device);
+    let mut pin = IntPin {
+      samples: std::cell::Cell::new(0),
+      low_from: usize::MAX,
+    };
+
+    let result =
+      ic.wait_for_interrupt(&mut pin, Duration::ZERO, Duration::ZERO);
+
+    assert!(result.is_err());
+    assert_eq!(
+      ic.device().register(Register::STATUS_INTERRUPT),
+      Mask::ENG1_INT.bits()
+    );
+  }
+
+  #[test]
+  fn dump_registers_covers_only_control_registers() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::MISC, 0b0100_0000);
+    let mut ic = LP55231::new(device);
+
+    let registers = ic.dump_registers().unwrap();
+
+    let dumped: Vec<_> =
+      registers.iter().map(|(register, _)| *register).collect();
+    assert!(!dumped.contains(&Register::RESET));
+    assert!(!dumped.contains(&Register::PROG_MEM_BASE));
+    assert_eq!(dumped.len(), Register::ALL.len() - 2);
+    assert_eq!(dumped.last(), Some(&Register::PROG_MEM_PAGE_SEL));
+    assert!(registers.contains(&(Register::MISC, 0b0100_0000)));
+  }
+
+  #[test]
+  fn run_once_runs_engine_free_until_interrupt() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::ENABLE_ENGINE_CNTRL1, 0b0100_0000);
+    device.set_register(Register::STATUS_INTERRUPT, Mask::ENG2_INT.bits());
+    let mut ic = LP55231::new(device);
+
+    ic.run_once(Engine::E2, Some(4), Duration::ZERO, Duration::from_secs(1))
+      .unwrap();
+
+    // Entry point and program counter, then OP MODE run (0b10) and EXEC
+    // free (0b10) for engine 2; never EXEC execute once (0b11), which would
+    // only run a single instruction.
+    assert_eq!(
+      ic.device().writes(),
+      [
+        (Register::ENG2_PROG_START_ADDR as u8, 4),
+        (Register::ENGINE2_PC as u8, 4),
+        (Register::ENGINE_CNTRL_2 as u8, 0b0000_1000),
+        (Register::ENABLE_ENGINE_CNTRL1 as u8, 0b0100_1000),
+      ]
+    );
+  }
+
+  #[test]
+  fn run_once_times_out_while_engine_runs() {
+    let mut ic = LP55231::new(MockI2c::new());
+
+    let result = ic.run_once(Engine::E1, None, Duration::ZERO, Duration::ZERO);
+
+    assert!(result.is_err());
+    ic.device()
+      .expect_write(Register::ENGINE_CNTRL_2, 0b0010_0000);
+    ic.device()
+      .expect_write(Register::ENABLE_ENGINE_CNTRL1, 0b0010_0000);
+  }
+
+  #[test]
+  fn retry_recovers_from_transient_errors() {
+    let mut device = MockI2c::new();
+    device.set_register(Register::MISC, 0b0100_0000);
+    device.fail_next(2, EIO);
+    let mut ic = LP55231::new(device);
+    ic.max_retries = 3;
+    ic.retry_backoff = Duration::ZERO;
+
+    assert_eq!(ic.read_register(Register::MISC).unwrap(), 0b0100_0000);
+    // Two failed attempts, then a successful one.
+    assert_eq!(ic.device().failures(), 2);
+  }
+
+  #[test]
+  fn retry_gives_up_after_max_retries() {
+    let mut device = MockI2c::new();
+    device.fail_next(10, EIO);
+    let mut ic = LP55231::new(device);
+    ic.max_retries = 2;
+    ic.retry_backoff = Duration::ZERO;
+
+    assert!(ic.write_register(Register::D1_PWM, 1).is_err());
+    assert_eq!(ic.device().failures(), 3);
+    ic.device().expect_no_write(Register::D1_PWM);
+  }
+
+  #[test]
+  fn retry_is_disabled_by_default() {
+    let mut device = MockI2c::new();
+    device.fail_next(1, EIO);
+    let mut ic = LP55231::new(device);
+
+    assert!(ic.read_register(Register::MISC).is_err());
+    assert_eq!(ic.device().failures(), 1);
+  }
+
+  #[test]
+  fn retry_fails_immediately_on_enxio() {
+    let mut device = MockI2c::new();
+    device.fail_next(2, ENXIO);
+    let mut ic = LP55231::new(device);
+    ic.max_retries = 3;
+    ic.retry_backoff = Duration::ZERO;
+
+    let error = ic.read_register(Register::MISC).unwrap_err();
+    assert_eq!(ic.device().failures(), 1);
+    match error {
+      LinuxI2CError::Io(error) => assert_eq!(error.raw_os_error(), Some(ENXIO)),
+      LinuxI2CError::Nix(error) => panic!("unexpected error {:?}", error),
+    }
+  }
+
+  #[test]
+  fn validated_program_loads_into_program_memory() {
+    let program = [
+      Instruction::map_channels(&[Channel::D1]),
+      Instruction::mux_map_start(0),
+      Instruction::mux_ld_end(0),
+      Instruction::set_pwm(255),
+      Instruction::end(false, false),
+    ];
+    let mut ic = LP55231::new(MockI2c::new());
+
+    program::validate(&program).unwrap();
+    ic.load_program(&program).unwrap();
+
+    ic.device()
+      .expect_write(Register::PROG_MEM_BASE, program[0].msb);
+    assert_eq!(ic.device().program_page(0), page_bytes(&program));
+    assert_eq!(
+      program::validate(&program[..4]),
+      Err(ValidationError::NoTerminator)
+    );
+    assert_eq!(
+      program::validate(&[Instruction::mux_map_start(5), Instruction::END]),
+      Err(ValidationError::AddressOutOfRange {
+        index: 0,
+        address: 5
+      })
+    );
+  }
+
+  #[test]
+  fn breathe_program_runs_from_program_memory() {
+    let period = Duration::from_secs(2);
+    let program = program::breathe(Channel::D3, period, 0, 255);
+    let mut ic = LP55231::new(MockI2c::new());
+
+    ic.run_program(&program, [Some(0), None, None]).unwrap();
+
+    let device = ic.device();
+    device.expect_write(Register::PROG_MEM_BASE, program[0].msb);
+    assert_eq!(device.program_page(0), page_bytes(&program[..16]));
+    assert_eq!(device.program_page(1), page_bytes(&program[16..]));
+    device.expect_write(Register::ENG1_PROG_START_ADDR, 0);
+
+    // One full cycle: setup, ramps up to max, ramps back down to min.
+    let mut simulator = Simulator::new(program.clone());
+    let steps = simulator.run(program.len() - 1);
+    let peak = steps.iter().map(|step| step.pwm).max();
+    assert_eq!(peak, Some(255));
+    assert_eq!(steps.last().unwrap().pwm, 0);
+    let error = simulator.elapsed().abs_diff(period);
+    assert!(error < period / 10, "cycle took {:?}", simulator.elapsed());
+  }
+
+  #[test]
+  fn blink_program_loads_and_repeats() {
+    let second = Duration::from_secs(1);
+    let program = program::blink(Channel::D5, second, second, 200, Some(3));
+    let mut ic = LP55231::new(MockI2c::new());
+
+    ic.load_program(&program).unwrap();
+
+    let device = ic.device();
+    device.expect_write(Register::PROG_MEM_BASE, program[0].msb);
+    let pages: Vec<u8> = (0..2)
+      .flat_map(|page| device.program_page(page).to_vec())
+      .collect();
+    let loaded: Vec<u8> =
+      program.iter().flat_map(Instruction::as_bytes).collect();
+    assert_eq!(pages[..loaded.len()], loaded);
+
+    // Three blinks of one second on and one second off, then the end.
+    let mut simulator = Simulator::new(program);
+    let steps = simulator.run(100);
+    let on = steps
+      .iter()
+      .filter(|step| step.instruction == DecodedInstruction::SetPwm(200));
+    assert_eq!(on.count(), 3);
+    assert!(simulator.is_halted());
+    assert_eq!(simulator.pwm(), 0);
+    let error = simulator.elapsed().abs_diff(6 * second);
+    assert!(error < second / 10, "blinks took {:?}", simulator.elapsed());
+  }
+
+  #[test]
+  fn prescale_for_duration_picks_written_wait_encoding() {
+    let cases = [
+      (Duration::from_millis(5), PreScale::CT0_488, 10, 0b0001_0100),
+      (
+        Duration::from_millis(100),
+        PreScale::CT15_625,
+        6,
+        0b0100_1100,
+      ),
+    ];
+
+    for (step, pre_scale, cycles, msb) in cases {
+      assert_eq!(PreScale::for_duration(step), pre_scale);
+      let error = step.abs_diff(pre_scale.step_time() * cycles as u32);
+      assert!(error < pre_scale.step_time());
+
+      let mut ic = LP55231::new(MockI2c::new());
+      let wait = Instruction::wait(PreScale::for_duration(step), cycles);
+      ic.write_program_instruction(0, &wait).unwrap();
+
+      ic.device().expect_write(Register::PROG_MEM_BASE, msb);
+      assert_eq!(
+        ic.device().writes()[1],
+        (Register::PROG_MEM_BASE as u8 + 1, 0)
+      );
+    }
+  }
+
+  #[test]
+  fn hsv_color_writes_rgb_group_pwm() {
+    let group = RgbGroup {
+      r: Channel::D7,
+      g: Channel::D1,
+      b: Channel::D2,
+    };
+    let mut ic = LP55231::new(MockI2c::new());
+
+    let [r, g, b] = color::hsv_to_pwm(30.0, 1.0, 1.0);
+    ic.set_rgb(&group, r, g, b).unwrap();
+
+    let device = ic.device();
+    device.expect_write(Register::D7_PWM, 255);
+    device.expect_write(Register::D1_PWM, 128);
+    device.expect_write(Register::D2_PWM, 0);
+    device.expect_no_write(Register::D3_PWM);
+  }
+
+  #[test]
+  fn set_channel_current_ma_writes_tenths_of_milliamps() {
+    let mut ic = LP55231::new(MockI2c::new());
+
+    ic.set_channel_current_ma(Channel::D4, 17.5).unwrap();
+    ic.device().expect_write(Register::D4_CURRENT_CONTROL, 175);
+    assert_eq!(
+      current_to_ma(ic.get_channel_current(Channel::D4).unwrap()),
+      17.5
+    );
+
+    ic.device_mut().clear_writes();
+    assert!(ic.set_channel_current_ma(Channel::D4, 30.0).is_err());
+    assert!(ic.set_channel_current_ma(Channel::D4, -1.0).is_err());
+    ic.device().expect_no_write(Register::D4_CURRENT_CONTROL);
+  }
+
+  #[test]
+  fn block_writes_follow_tracked_auto_increment_state() {
+    let pwm = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    let mut device = MockI2c::new();
+    device.set_register(Register::MISC, Mask::EN_AUTO_INCR.bits());
+    let mut ic = LP55231::new(device);
+
+    // Auto-increment turned off behind the driver's back, then through it:
+    // either way, no block write may hit D1 PWM repeatedly.
+    let disable: [fn(&mut LP55231<MockI2c>); 2] = [
+      |ic| ic.device_mut().set_register(Register::MISC, 0),
+      |ic| ic.set_misc_settings(Misc::default()).unwrap(),
+    ];
+    for disable in disable {
+      ic.set_auto_increment(true).unwrap();
+      assert!(ic.is_auto_increment_enabled().unwrap());
+      disable(&mut ic);
+      ic.device_mut().clear_writes();
+
+      ic.set_all_pwm(pwm).unwrap();
+
+      for channel in Channel::all() {
+        let register = Register::pwm_for(channel);
+        assert_eq!(ic.device().register(register), pwm[channel.as_index()]);
+      }
+      let d1_writes = ic
+        .device()
+        .writes()
+        .iter()
+        .filter(|(address, _)| *address == Register::D1_PWM as u8)
+        .count();
+      assert_eq!(d1_writes, 1);
+    }
+  }
+
+  #[test]
+  fn device_group_distributes_frame_across_devices() {
+    let mut chips = DeviceGroup::new(vec![
+      LP55231::new(MockI2c::new()),
+      LP55231::new(MockI2c::new()),
+    ]);
+    let frame: Vec<u8> = (1..=18).collect();
+
+    chips.set_all_pwm(&frame).unwrap();
+
+    for (device, values) in chips.devices.iter().zip(frame.chunks(9)) {
+      for channel in Channel::all() {
+        device
+          .device()
+          .expect_write(Register::pwm_for(channel), values[channel.as_index()]);
+      }
+    }
+
+    for chip in &mut chips.devices {
+      chip.device_mut().clear_writes();
+    }
+    assert!(chips.set_all_pwm(&frame[..17]).is_err());
+    for chip in &chips.devices {
+      chip.device().expect_no_write(Register::D1_PWM);
+    }
+  }
+
+  #[test]
+  fn animator_pushes_frames_in_order() {
+    let mut ic = LP55231::new(MockI2c::new());
+    let frames = [[10; 9], [20; 9], [30; 9]];
+
+    let report = Animator::new(20).run(&mut ic, frames).unwrap();
+
+    assert_eq!(report.frames_pushed + report.frames_dropped, frames.len());
+    let d1_writes: Vec<u8> = ic
+      .device()
+      .writes()
+      .iter()
+      .filter(|(address, _)| *address == Register::D1_PWM as u8)
+      .map(|(_, value)| *value)
+      .collect();
+    assert_eq!(d1_writes.len(), report.frames_pushed);
+    assert!(d1_writes.windows(2).all(|pair| pair[0] < pair[1]));
+    for channel in Channel::all() {
+      ic.device().expect_write(Register::pwm_for(channel), 30);
+    }
+  }
 }
@@ -69,9 +69,20 @@ bitflags! {
     const ENG2_INT = 0b0000_0010;
     const ENG3_INT = 0b0000_0001;
 
+    // 3B, INT/GPO
+    const INT_CONF = 0b0000_0100;
+    const GPO = 0b0000_0010;
+    const INT_GPO = 0b0000_0001;
+
     // 3D, RESET
     const RESET = 0b1111_1111;
 
+    // 3E, TEMP ADC CONTROL
+    const TEMP_MEAS_BUSY = 0b1000_0000;
+    const EN_TEMP_SENSOR = 0b0000_0100;
+    const CONTINUOUS_CONV = 0b0000_0010;
+    const SEL_EXT_TEMP = 0b0000_0001;
+
     // 4F, PROG MEM PAGE SELECT
     const PAGE_SEL = 0b0000_0111;
   }
@@ -84,17 +95,38 @@ impl Mask {
   /// - A mask `0b0000_1100`
   /// - A value `0b10`
   /// - A byte `0b1111_1111`
+  ///
   /// Then:
   /// - `mask.apply(value, byte)` will produce `0b1111_1011`
+  ///
+  /// Value bits that don't fit the mask are discarded rather than spilling
+  /// into adjacent bits of `byte`.
   pub fn apply(&self, value: u8, to_byte: u8) -> u8 {
     let byte_with_mask_bits_cleared = to_byte & !self.bits();
-    let value_moved_to_mask_bits = value << self.bits().trailing_zeros();
 
-    byte_with_mask_bits_cleared | value_moved_to_mask_bits
+    byte_with_mask_bits_cleared | self.with(value)
   }
 
+  /// Shift the specified `value` to the [Mask] bits.
+  ///
+  /// Value bits that don't fit the mask are discarded.
   pub fn with(&self, value: u8) -> u8 {
-    value << self.bits().trailing_zeros()
+    (value << self.bits().trailing_zeros()) & self.bits()
+  }
+
+  /// Number of bits in the [Mask] field; assumes the bits are contiguous.
+  ///
+  /// Example; given a mask `0b0001_1000`, `mask.width()` will produce `2`.
+  pub fn width(&self) -> u32 {
+    self.bits().count_ones()
+  }
+
+  /// Largest value that fits the [Mask] bits (see [`Self::with`]).
+  ///
+  /// Example; given a mask `0b0001_1000`, `mask.max_value()` will produce
+  /// `0b11`.
+  pub fn max_value(&self) -> u8 {
+    self.value(self.bits())
   }
 
   /// Returns the value set at the mask bits.
@@ -135,6 +167,14 @@ impl Mask {
     }
   }
 
+  pub fn int_for(engine: Engine) -> Mask {
+    match engine {
+      Engine::E1 => Mask::ENG1_INT,
+      Engine::E2 => Mask::ENG2_INT,
+      Engine::E3 => Mask::ENG3_INT,
+    }
+  }
+
   pub fn ratiometric_dimming_for(channel: Channel) -> Mask {
     match channel {
       Channel::D1 => Mask::D1_RATIO_EN,
@@ -163,3 +203,51 @@ impl Mask {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Multi-bit fields at the low (bits 0-1), middle (bits 3-4) and high
+  // (bits 6-7) end of a byte.
+  const FIELDS: [Mask; 3] = [Mask::ENGINE3_MODE, Mask::CP_MODE, Mask::MAPPING];
+
+  #[test]
+  fn apply_sets_field_bits() {
+    assert_eq!(Mask::CP_MODE.apply(0b10, 0), 0b0001_0000);
+    assert_eq!(Mask::MAPPING.apply(0b01, 0), 0b0100_0000);
+    assert_eq!(Mask::ENGINE3_MODE.apply(0b11, 0), 0b0000_0011);
+  }
+
+  #[test]
+  fn apply_discards_out_of_range_values() {
+    assert_eq!(Mask::CP_MODE.apply(0b111, 0), 0b0001_1000);
+    assert_eq!(Mask::MAPPING.apply(0b111, 0), 0b1100_0000);
+    assert_eq!(Mask::ENGINE3_MODE.apply(0b1111_1111, 0), 0b0000_0011);
+  }
+
+  #[test]
+  fn apply_preserves_surrounding_bits() {
+    for mask in FIELDS {
+      for value in 0..=mask.max_value() {
+        assert_eq!(mask.apply(value, 0xFF) & !mask.bits(), !mask.bits());
+        assert_eq!(mask.apply(value, 0x00) & !mask.bits(), 0);
+      }
+    }
+    assert_eq!(Mask::CP_MODE.apply(0b01, 0xFF), 0b1110_1111);
+    assert_eq!(Mask::MAPPING.apply(0b10, 0b0011_1111), 0b1011_1111);
+    assert_eq!(Mask::ENGINE3_MODE.apply(0b00, 0xFF), 0b1111_1100);
+  }
+
+  #[test]
+  fn with_and_value_round_trip() {
+    for mask in FIELDS {
+      assert_eq!(mask.width(), 2);
+      assert_eq!(mask.max_value(), 0b11);
+      for value in 0..=mask.max_value() {
+        assert_eq!(mask.value(mask.with(value)), value);
+        assert_eq!(mask.value(mask.apply(value, 0xFF)), value);
+      }
+    }
+  }
+}
@@ -1,4 +1,10 @@
-use crate::Channel;
+use std::{
+  array::TryFromSliceError, f32::consts::PI, fmt, ops::Range, time::Duration,
+};
+
+use linux_embedded_hal::i2cdev::linux::LinuxI2CError;
+
+use crate::{Channel, Engine};
 
 /// Maximum number of instructions supported by programming engine.
 ///
@@ -10,6 +16,10 @@ pub const INSTRUCTIONS_PER_PAGE: u8 = 16;
 pub const MAX_PAGES: u8 = 6;
 /// Number of variables supported by programming engine.
 pub const MAX_VARS: u8 = 4;
+/// Maximum number of instructions conditional jumps can skip (5 bits).
+pub const MAX_JUMP_SKIP: u8 = 31;
+/// Maximum loop count of a branch (6 bits); 0 loops forever.
+pub const MAX_LOOP_COUNT: u8 = 63;
 
 /// Programming engine variables.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -31,6 +41,38 @@ pub enum PreScale {
   CT15_625 = 1,
 }
 
+impl PreScale {
+  /// Duration of a single cycle; ramp steps and waits last a number of these
+  /// cycles (see [`Instruction::ramp`] and [`Instruction::wait`]).
+  pub fn step_time(&self) -> Duration {
+    match self {
+      PreScale::CT0_488 => Duration::from_nanos(488_281),
+      PreScale::CT15_625 => Duration::from_micros(15_625),
+    }
+  }
+
+  /// Pre-scale whose achievable step times (1 to 31 cycles) come closest to
+  /// `target_step`, favoring the finer [`PreScale::CT0_488`] on ties.
+  pub fn for_duration(target_step: Duration) -> Self {
+    let error = |pre_scale: PreScale| {
+      let cycles = pre_scale.cycles_for(target_step);
+      target_step.abs_diff(pre_scale.step_time() * cycles as u32)
+    };
+
+    if error(PreScale::CT0_488) <= error(PreScale::CT15_625) {
+      PreScale::CT0_488
+    } else {
+      PreScale::CT15_625
+    }
+  }
+
+  // Number of cycles closest to `duration`, in range [1:31].
+  fn cycles_for(&self, duration: Duration) -> u8 {
+    let cycles = duration.as_secs_f64() / self.step_time().as_secs_f64();
+    cycles.round().clamp(1.0, MAX_STEP_CYCLES as f64) as u8
+  }
+}
+
 /// Ramp direction.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(u8)]
@@ -39,19 +81,84 @@ pub enum Direction {
   Down = 1,
 }
 
+/// Fields of a ramp instruction whose step time and number of increments are
+/// read from [variables](Variable); mirrors
+/// [`DecodedInstruction::RampFromVars`](crate::DecodedInstruction::RampFromVars).
+///
+/// Converts into an [`Instruction`]:
+///
+/// |Bit  |15|14|13|12|11|10|09|08|07|06|05|04|03|02|01|00|
+/// |-----|--|--|--|--|--|--|--|--|--|--|--|--|--|--|--|--|
+/// |Value| 1| 0| 0| 0| 0| 1| 0| 0| 0| P| D| 0| S| S| I| I|
+///
+/// Where `P` is the pre-scale, `D` the direction, `S` the step time variable
+/// and `I` the increments variable.
+///
+/// NB: With [`PreScale::CT15_625`], [`Direction::Down`] and `step_time_var`
+/// [`Variable::A`], the word is that of
+/// [`Instruction::set_pwm_from_var`], which takes precedence.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RampFromVars {
+  pub cycle_time: PreScale,
+  pub direction: Direction,
+  /// Variable holding the number of cycles per step.
+  pub step_time_var: Variable,
+  /// Variable holding the number of steps.
+  pub increments_var: Variable,
+}
+
+impl From<RampFromVars> for Instruction {
+  fn from(ramp: RampFromVars) -> Self {
+    let mut lsb = (ramp.step_time_var as u8) << 2;
+    lsb |= ramp.increments_var as u8;
+    lsb |= (ramp.cycle_time as u8) << 6;
+    lsb |= (ramp.direction as u8) << 5;
+
+    Self {
+      msb: 0b1000_0100,
+      lsb,
+    }
+  }
+}
+
 /// Representation for a programming engine instruction.
 ///
 /// Refer to spec sections 7.6.3 through 7.6.7
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Instruction {
   pub msb: u8,
   pub lsb: u8,
 }
 
 impl Instruction {
+  /// [`end`](Self::end) without interrupt, resetting the program counter.
+  pub const END: Instruction = Instruction {
+    msb: 0b1100_1000,
+    lsb: 0b0000_0000,
+  };
+  /// [`rst`](Self::rst).
+  pub const RST: Instruction = Instruction {
+    msb: 0b0000_0000,
+    lsb: 0b0000_0000,
+  };
+  /// No-op; the shortest [`wait`](Self::wait) (one 0.488ms cycle).
+  ///
+  /// A wait of zero cycles can't be used, as it encodes the same as
+  /// [`RST`](Self::RST).
+  pub const NOP: Instruction = Instruction {
+    msb: 0b0000_0010,
+    lsb: 0b0000_0000,
+  };
+
   /// Word (u16) representation for an instruction.
   pub fn as_u16(&self) -> u16 {
     (self.msb as u16) << 8 | self.lsb as u16
   }
+
+  /// Byte representation for an instruction, most significant byte first.
+  pub fn as_bytes(&self) -> [u8; 2] {
+    [self.msb, self.lsb]
+  }
 }
 
 impl From<u16> for Instruction {
@@ -64,6 +171,24 @@ impl From<u16> for Instruction {
   }
 }
 
+impl From<[u8; 2]> for Instruction {
+  /// Convert bytes (most significant first) to an [Instruction].
+  fn from([msb, lsb]: [u8; 2]) -> Self {
+    Self { msb, lsb }
+  }
+}
+
+impl TryFrom<&[u8]> for Instruction {
+  type Error = TryFromSliceError;
+
+  /// Convert a slice of bytes (most significant first) to an [Instruction].
+  ///
+  /// Fails unless the slice is exactly 2 bytes long.
+  fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+    <[u8; 2]>::try_from(value).map(Self::from)
+  }
+}
+
 impl Instruction {
   // Driver instructions
 
@@ -83,23 +208,26 @@ impl Instruction {
     }
   }
 
+  /// Same as [`RampFromVars`], where `pre_scale` selects
+  /// [`PreScale::CT15_625`] and `ascending` selects [`Direction::Up`].
   pub fn ramp_from_vars(
     pre_scale: bool,
     ascending: bool,
     step_time_var: Variable,
     increments_var: Variable,
   ) -> Self {
-    let mut lsb = ((step_time_var as u8) << 2) | (increments_var as u8);
-    if pre_scale {
-      lsb |= 1 << 6;
-    }
-    if ascending {
-      lsb |= 1 << 5;
-    }
-    Self {
-      msb: 0b1000_0100,
-      lsb,
-    }
+    Self::from(RampFromVars {
+      cycle_time: match pre_scale {
+        false => PreScale::CT0_488,
+        true => PreScale::CT15_625,
+      },
+      direction: match ascending {
+        true => Direction::Up,
+        false => Direction::Down,
+      },
+      step_time_var,
+      increments_var,
+    })
   }
 
   pub fn set_pwm(value: u8) -> Self {
@@ -116,6 +244,56 @@ impl Instruction {
     }
   }
 
+  /// Increase the current PWM value by `value`, saturating at 255.
+  ///
+  /// The instruction set has no relative form of `set_pwm`, so this is a
+  /// [`Self::ramp`] of `value` steps at the shortest step time (0.488ms).
+  pub fn increment_pwm(value: u8) -> Self {
+    Self::ramp(PreScale::CT0_488, 1, Direction::Up, value)
+  }
+
+  /// Decrease the current PWM value by `value`, saturating at 0.
+  ///
+  /// See [`Self::increment_pwm`].
+  pub fn decrement_pwm(value: u8) -> Self {
+    Self::ramp(PreScale::CT0_488, 1, Direction::Down, value)
+  }
+
+  /// Increase the current PWM value by the value of `increments_var`.
+  ///
+  /// Variable form of [`Self::increment_pwm`]; `step_time_var` holds the
+  /// number of 0.488ms cycles per step (1 for the shortest step time).
+  pub fn increment_pwm_from_var(
+    step_time_var: Variable,
+    increments_var: Variable,
+  ) -> Self {
+    Self::pwm_offset_from_vars(Direction::Up, step_time_var, increments_var)
+  }
+
+  /// Decrease the current PWM value by the value of `increments_var`.
+  ///
+  /// See [`Self::increment_pwm_from_var`].
+  pub fn decrement_pwm_from_var(
+    step_time_var: Variable,
+    increments_var: Variable,
+  ) -> Self {
+    Self::pwm_offset_from_vars(Direction::Down, step_time_var, increments_var)
+  }
+
+  // Ramp from variables with the shortest pre-scale.
+  fn pwm_offset_from_vars(
+    direction: Direction,
+    step_time_var: Variable,
+    increments_var: Variable,
+  ) -> Self {
+    Self::from(RampFromVars {
+      cycle_time: PreScale::CT0_488,
+      direction,
+      step_time_var,
+      increments_var,
+    })
+  }
+
   pub fn wait(cycle_time: PreScale, cycles: u8) -> Self {
     let mut msb = cycles << 1; // TODO check bounds
     msb |= (cycle_time as u8) << 6;
@@ -145,6 +323,26 @@ impl Instruction {
     Self::from(map_bits)
   }
 
+  /// Create LED engine-to-LED mapping instruction from a bitmask, where bit 0
+  /// maps D1 through bit 8 mapping D9 (see [`Self::map_channels`]).
+  ///
+  /// Bits 9 through 15 are ignored.
+  pub fn map_channels_mask(mask: u16) -> Self {
+    Self::from(mask & 0b0000_0001_1111_1111)
+  }
+
+  /// Create LED engine-to-LED mapping instruction from per-channel flags,
+  /// ordered D1 through D9 (see [`Self::map_channels`]).
+  pub fn map_channels_array(channels: [bool; 9]) -> Self {
+    let mut map_bits = 0b0000_0000_0000_0000;
+    for (index, mapped) in channels.iter().enumerate() {
+      if *mapped {
+        map_bits |= 1 << index;
+      }
+    }
+    Self::from(map_bits)
+  }
+
   pub fn mux_ld_start(sram_address: u8) -> Self {
     Self {
       msb: 0b1001_1110,
@@ -266,6 +464,8 @@ impl Instruction {
     }
   }
 
+  /// `num_instructions_to_skip` must not exceed [`MAX_JUMP_SKIP`]; larger
+  /// values spill into the opcode bits. See [`Self::try_jne`].
   pub fn jne(
     num_instructions_to_skip: u8,
     var_1: Variable,
@@ -274,11 +474,13 @@ impl Instruction {
     let mut instr: u16 = 0b1000_1000_0000_0000;
     instr |= var_1 as u16;
     instr |= (var_2 as u16) << 2;
-    instr |= (num_instructions_to_skip as u16) << 4; // TODO check bounds.
+    instr |= (num_instructions_to_skip as u16) << 4;
 
     Self::from(instr)
   }
 
+  /// `num_instructions_to_skip` must not exceed [`MAX_JUMP_SKIP`]; larger
+  /// values spill into the opcode bits. See [`Self::try_jl`].
   pub fn jl(
     num_instructions_to_skip: u8,
     var_1: Variable,
@@ -287,11 +489,13 @@ impl Instruction {
     let mut instr: u16 = 0b1000_1010_0000_0000;
     instr |= var_2 as u16;
     instr |= (var_1 as u16) << 2;
-    instr |= (num_instructions_to_skip as u16) << 4; // TODO check bounds.
+    instr |= (num_instructions_to_skip as u16) << 4;
 
     Self::from(instr)
   }
 
+  /// `num_instructions_to_skip` must not exceed [`MAX_JUMP_SKIP`]; larger
+  /// values spill into the opcode bits. See [`Self::try_jge`].
   pub fn jge(
     num_instructions_to_skip: u8,
     var_1: Variable,
@@ -300,11 +504,13 @@ impl Instruction {
     let mut instr: u16 = 0b1000_1100_0000_0000;
     instr |= var_2 as u16;
     instr |= (var_1 as u16) << 2;
-    instr |= (num_instructions_to_skip as u16) << 4; // TODO check bounds.
+    instr |= (num_instructions_to_skip as u16) << 4;
 
     Self::from(instr)
   }
 
+  /// `num_instructions_to_skip` must not exceed [`MAX_JUMP_SKIP`]; larger
+  /// values spill into the opcode bits. See [`Self::try_je`].
   pub fn je(
     num_instructions_to_skip: u8,
     var_1: Variable,
@@ -313,11 +519,55 @@ impl Instruction {
     let mut instr: u16 = 0b1000_1110_0000_0000;
     instr |= var_2 as u16;
     instr |= (var_1 as u16) << 2;
-    instr |= (num_instructions_to_skip as u16) << 4; // TODO check bounds.
+    instr |= (num_instructions_to_skip as u16) << 4;
 
     Self::from(instr)
   }
 
+  /// Same as [`Self::jne`], but fails if `num_instructions_to_skip` exceeds
+  /// [`MAX_JUMP_SKIP`].
+  pub fn try_jne(
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  ) -> Result<Self, LinuxI2CError> {
+    check_jump_skip(num_instructions_to_skip)?;
+    Ok(Self::jne(num_instructions_to_skip, var_1, var_2))
+  }
+
+  /// Same as [`Self::jl`], but fails if `num_instructions_to_skip` exceeds
+  /// [`MAX_JUMP_SKIP`].
+  pub fn try_jl(
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  ) -> Result<Self, LinuxI2CError> {
+    check_jump_skip(num_instructions_to_skip)?;
+    Ok(Self::jl(num_instructions_to_skip, var_1, var_2))
+  }
+
+  /// Same as [`Self::jge`], but fails if `num_instructions_to_skip` exceeds
+  /// [`MAX_JUMP_SKIP`].
+  pub fn try_jge(
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  ) -> Result<Self, LinuxI2CError> {
+    check_jump_skip(num_instructions_to_skip)?;
+    Ok(Self::jge(num_instructions_to_skip, var_1, var_2))
+  }
+
+  /// Same as [`Self::je`], but fails if `num_instructions_to_skip` exceeds
+  /// [`MAX_JUMP_SKIP`].
+  pub fn try_je(
+    num_instructions_to_skip: u8,
+    var_1: Variable,
+    var_2: Variable,
+  ) -> Result<Self, LinuxI2CError> {
+    check_jump_skip(num_instructions_to_skip)?;
+    Ok(Self::je(num_instructions_to_skip, var_1, var_2))
+  }
+
   pub fn ld(target_var: Variable, value: u8) -> Self {
     Self {
       msb: 0b1001_0000 | ((target_var as u8) << 2),
@@ -343,10 +593,10 @@ impl Instruction {
     }
   }
 
-  pub fn sub_numerical(target_var: Variable, value: Variable) -> Self {
+  pub fn sub_numerical(target_var: Variable, value: u8) -> Self {
     Self {
       msb: 0b1001_0010 | ((target_var as u8) << 2),
-      lsb: value as u8,
+      lsb: value,
     }
   }
 
@@ -371,3 +621,850 @@ fn check_addr(addr: u8) -> u8 {
   }
   addr
 }
+
+fn check_jump_skip(skip: u8) -> Result<(), LinuxI2CError> {
+  if skip <= MAX_JUMP_SKIP {
+    return Ok(());
+  }
+
+  Err(LinuxI2CError::Io(std::io::Error::other(format!(
+    "invalid jump skip ({}); must be in range [0:{}]",
+    skip, MAX_JUMP_SKIP
+  ))))
+}
+
+/// Reasons a program can fail [validation](validate).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ValidationError {
+  /// Program has no instructions.
+  Empty,
+  /// Program has more than [`MAX_INSTRUCTIONS`] instructions.
+  TooManyInstructions(usize),
+  /// Last instruction neither ends (`end`, `rst`) nor loops (`branch`).
+  NoTerminator,
+  /// Mux instruction at `index` points to an `address` past the end of the
+  /// program.
+  AddressOutOfRange { index: usize, address: u8 },
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Empty => write!(f, "program is empty"),
+      Self::TooManyInstructions(count) => write!(
+        f,
+        "too many instructions ({}); limit is {}",
+        count, MAX_INSTRUCTIONS
+      ),
+      Self::NoTerminator => {
+        write!(
+          f,
+          "program must finish with an end, rst or branch instruction"
+        )
+      }
+      Self::AddressOutOfRange { index, address } => write!(
+        f,
+        "instruction {} points to address {}, past the end of the program",
+        index, address
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check a program for obvious mistakes before loading it.
+///
+/// Checks that the program:
+/// - is not empty, and fits in program memory (see [`MAX_INSTRUCTIONS`])
+/// - finishes with an [`end`](Instruction::end), [`rst`](Instruction::rst) or
+///   loops via [`branch`](Instruction::branch)
+/// - has no mux instructions (e.g. [`Instruction::mux_map_start`]) pointing
+///   past its last instruction
+///
+/// Passing validation doesn't guarantee the program behaves as intended.
+pub fn validate(instructions: &[Instruction]) -> Result<(), ValidationError> {
+  let Some(last) = instructions.last() else {
+    return Err(ValidationError::Empty);
+  };
+
+  if instructions.len() > MAX_INSTRUCTIONS as usize {
+    return Err(ValidationError::TooManyInstructions(instructions.len()));
+  }
+
+  if !(is_end(last) || is_rst(last) || is_branch(last)) {
+    return Err(ValidationError::NoTerminator);
+  }
+
+  for (index, instruction) in instructions.iter().enumerate() {
+    if let Some(address) = mux_address(instruction) {
+      if address as usize >= instructions.len() {
+        return Err(ValidationError::AddressOutOfRange { index, address });
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn is_end(instruction: &Instruction) -> bool {
+  instruction.msb & 0b1110_0111 == 0b1100_0000 && instruction.lsb == 0
+}
+
+fn is_rst(instruction: &Instruction) -> bool {
+  instruction.as_u16() == 0
+}
+
+fn is_branch(instruction: &Instruction) -> bool {
+  let branch = instruction.msb & 0b1110_0000 == 0b1010_0000;
+  let branch_vars = instruction.msb & 0b1111_1110 == 0b1000_0110;
+  branch || branch_vars
+}
+
+// SRAM address of mux instructions that take one (mux_ld_start,
+// mux_map_start, mux_ld_end, mux_ld_addr and mux_map_addr).
+fn mux_address(instruction: &Instruction) -> Option<u8> {
+  match instruction.msb {
+    0b1001_1100 | 0b1001_1110 | 0b1001_1111 => {
+      Some(instruction.lsb & 0b0111_1111)
+    }
+    _ => None,
+  }
+}
+
+// Number of ramp segments approximating each half of a `breathe` cycle.
+const BREATHE_SEGMENTS: u8 = 8;
+// Maximum number of cycles per ramp step or wait (5 bits).
+const MAX_STEP_CYCLES: u8 = 31;
+
+/// Create a program breathing the specified [`Channel`] between `min` and
+/// `max` PWM values, in an endless loop, with a full cycle (`min` to `max`
+/// and back) taking `period`.
+///
+/// Brightness follows a sine wave, approximated with up to 8 ramps per half
+/// cycle; the [`PreScale`] and cycles per step of each ramp are computed from
+/// `period`. Step times are limited by the instruction set (about 0.5ms to
+/// 484ms per PWM step), so very short or long periods are clamped.
+///
+/// The program expects to be loaded at address 0 (e.g. via
+/// [`LP55231::run_program`](crate::LP55231::run_program)), and holds `min`
+/// if `min` and `max` are equal.
+pub fn breathe(
+  channel: Channel,
+  period: Duration,
+  min: u8,
+  max: u8,
+) -> Vec<Instruction> {
+  let (min, max) = (min.min(max), min.max(max));
+  let mut program = vec![
+    // 00. Mapping table.
+    Instruction::map_channels(&[channel]),
+    // 01-02. Activate the mapping table.
+    Instruction::mux_map_start(0),
+    Instruction::mux_ld_end(0),
+    // 03. Start at the bottom of the wave.
+    Instruction::set_pwm(min),
+  ];
+  if min == max {
+    program.push(Instruction::end(false, false));
+    return program;
+  }
+
+  // Split each half cycle into segments of (about) equal PWM steps, each
+  // lasting as long as the sine wave takes to cover them:
+  // pwm(t) = min + steps * (1 - cos(PI * t / half_period)) / 2
+  let steps = max - min;
+  let segments = steps.min(BREATHE_SEGMENTS);
+  let half_period = period.as_secs_f32() / 2.0;
+  let time_at = |pwm: u8| {
+    let level = 1.0 - 2.0 * pwm as f32 / steps as f32;
+    half_period * level.clamp(-1.0, 1.0).acos() / PI
+  };
+  let mut ramps = Vec::with_capacity(segments as usize);
+  for segment in 0..segments {
+    let start = (segment as u16 * steps as u16 / segments as u16) as u8;
+    let end = ((segment as u16 + 1) * steps as u16 / segments as u16) as u8;
+    let segment_steps = end - start;
+    let step_time = (time_at(end) - time_at(start)) / segment_steps as f32;
+    ramps.push((Duration::from_secs_f32(step_time.max(0.0)), segment_steps));
+  }
+
+  // 04... Ramp up, then down mirroring the way up.
+  for (step_time, segment_steps) in &ramps {
+    program.push(ramp_for(*step_time, Direction::Up, *segment_steps));
+  }
+  for (step_time, segment_steps) in ramps.iter().rev() {
+    program.push(ramp_for(*step_time, Direction::Down, *segment_steps));
+  }
+
+  // Loop back to the first ramp.
+  program.push(Instruction::branch(4, 0));
+
+  program
+}
+
+/// Create a program blinking the specified [`Channel`]: on at `pwm` for `on`,
+/// then off for `off`.
+///
+/// Blinks `repeats` times before ending, or forever if `None`; at most 64
+/// times, as loop counts are limited to 6 bits.
+///
+/// Each wait instruction lasts up to about 484ms, so longer durations are
+/// split over several waits; `on` and `off` are clamped to about 20s each to
+/// fit in program memory.
+///
+/// Like [`breathe`], the program expects to be loaded at address 0.
+pub fn blink(
+  channel: Channel,
+  on: Duration,
+  off: Duration,
+  pwm: u8,
+  repeats: Option<u8>,
+) -> Vec<Instruction> {
+  let mut program = vec![
+    // 00. Mapping table.
+    Instruction::map_channels(&[channel]),
+    // 01-02. Activate the mapping table.
+    Instruction::mux_map_start(0),
+    Instruction::mux_ld_end(0),
+  ];
+  if repeats == Some(0) {
+    program.push(Instruction::set_pwm(0));
+    program.push(Instruction::end(false, false));
+    return program;
+  }
+
+  // 03... Blink.
+  program.push(Instruction::set_pwm(pwm));
+  program.extend(waits_for(on));
+  program.push(Instruction::set_pwm(0));
+  program.extend(waits_for(off));
+
+  // Loop back to the start of the blink; branches loop `loop_count` times
+  // after the first pass, or forever if 0.
+  match repeats {
+    None => program.push(Instruction::branch(3, 0)),
+    Some(repeats) => {
+      let loop_count = repeats.min(BLINK_MAX_REPEATS) - 1;
+      if loop_count > 0 {
+        program.push(Instruction::branch(3, loop_count));
+      }
+      program.push(Instruction::end(false, false));
+    }
+  }
+
+  program
+}
+
+// Maximum number of blinks (6-bit loop count, plus the first pass).
+const BLINK_MAX_REPEATS: u8 = 64;
+// Maximum number of wait instructions for each of the on and off phases of a
+// `blink`, leaving room for the other 7 instructions.
+const BLINK_MAX_WAITS: usize = 44;
+
+// Wait instructions adding up to `duration`, split evenly.
+fn waits_for(duration: Duration) -> Vec<Instruction> {
+  let max_wait = PreScale::CT15_625.step_time() * MAX_STEP_CYCLES as u32;
+  let count = (duration.as_secs_f32() / max_wait.as_secs_f32()).ceil() as usize;
+  let count = count.clamp(1, BLINK_MAX_WAITS);
+  let wait = duration / count as u32;
+  let pre_scale = PreScale::for_duration(wait);
+  let cycles = pre_scale.cycles_for(wait);
+  (0..count)
+    .map(|_| Instruction::wait(pre_scale, cycles))
+    .collect()
+}
+
+// Ramp with the pre-scale and cycles per step closest to `step_time`.
+fn ramp_for(
+  step_time: Duration,
+  direction: Direction,
+  steps: u8,
+) -> Instruction {
+  let pre_scale = PreScale::for_duration(step_time);
+  let cycles = pre_scale.cycles_for(step_time);
+  Instruction::ramp(pre_scale, cycles, direction, steps)
+}
+
+/// Builder for programs, resolving labels into instruction addresses.
+///
+/// Labels mark the address of the next instruction, and can be referenced
+/// (before or after being defined) by branches and conditional jumps, so
+/// that step numbers and skip counts needn't be computed by hand.
+///
+/// Example:
+/// ```
+/// # use ti_lp55231::{Channel, Instruction, PreScale, ProgramBuilder};
+/// let program = ProgramBuilder::new()
+///   .push(Instruction::map_channels(&[Channel::D1]))
+///   .push(Instruction::mux_map_start(0))
+///   .push(Instruction::mux_ld_end(0))
+///   .label("blink")
+///   .push(Instruction::set_pwm(255))
+///   .push(Instruction::wait(PreScale::CT15_625, 30))
+///   .push(Instruction::set_pwm(0))
+///   .push(Instruction::wait(PreScale::CT15_625, 30))
+///   .branch_to("blink", 0)
+///   .build()?;
+/// # Ok::<(), ti_lp55231::ProgramBuildError>(())
+/// ```
+#[derive(Default)]
+pub struct ProgramBuilder {
+  entries: Vec<Entry>,
+  labels: Vec<(String, u8)>,
+  duplicate_label: Option<String>,
+}
+
+// Instruction, or reference to a label to be resolved once the program is
+// built.
+enum Entry {
+  Instruction(Instruction),
+  Branch {
+    label: String,
+    loop_count: u8,
+  },
+  BranchVars {
+    label: String,
+    loop_count_var: Variable,
+  },
+  Jump {
+    label: String,
+    jump: fn(u8, Variable, Variable) -> Instruction,
+    var_1: Variable,
+    var_2: Variable,
+  },
+}
+
+impl ProgramBuilder {
+  /// Create an empty program builder.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Append an instruction.
+  pub fn push(mut self, instruction: Instruction) -> Self {
+    self.entries.push(Entry::Instruction(instruction));
+    self
+  }
+
+  /// Define a label for the address of the next instruction.
+  ///
+  /// Each label can only be defined once.
+  pub fn label(mut self, name: &str) -> Self {
+    if self.address_of(name).is_some() {
+      self.duplicate_label.get_or_insert_with(|| name.to_string());
+    } else {
+      let address = self.entries.len().min(u8::MAX as usize) as u8;
+      self.labels.push((name.to_string(), address));
+    }
+    self
+  }
+
+  /// Append a [`branch`](Instruction::branch) to the specified label.
+  ///
+  /// `loop_count` must not exceed [`MAX_LOOP_COUNT`] (0 loops forever).
+  pub fn branch_to(mut self, label: &str, loop_count: u8) -> Self {
+    self.entries.push(Entry::Branch {
+      label: label.to_string(),
+      loop_count,
+    });
+    self
+  }
+
+  /// Append a [`branch_vars`](Instruction::branch_vars) to the specified
+  /// label.
+  pub fn branch_vars_to(
+    mut self,
+    label: &str,
+    loop_count_var: Variable,
+  ) -> Self {
+    self.entries.push(Entry::BranchVars {
+      label: label.to_string(),
+      loop_count_var,
+    });
+    self
+  }
+
+  /// Append a [`jne`](Instruction::jne) skipping to the specified label.
+  ///
+  /// Jumps only skip forward, so the label must be defined after the jump.
+  pub fn jne_to(self, label: &str, var_1: Variable, var_2: Variable) -> Self {
+    self.jump_to(label, Instruction::jne, var_1, var_2)
+  }
+
+  /// Append a [`jl`](Instruction::jl) skipping to the specified label.
+  ///
+  /// See [`Self::jne_to`].
+  pub fn jl_to(self, label: &str, var_1: Variable, var_2: Variable) -> Self {
+    self.jump_to(label, Instruction::jl, var_1, var_2)
+  }
+
+  /// Append a [`jge`](Instruction::jge) skipping to the specified label.
+  ///
+  /// See [`Self::jne_to`].
+  pub fn jge_to(self, label: &str, var_1: Variable, var_2: Variable) -> Self {
+    self.jump_to(label, Instruction::jge, var_1, var_2)
+  }
+
+  /// Append a [`je`](Instruction::je) skipping to the specified label.
+  ///
+  /// See [`Self::jne_to`].
+  pub fn je_to(self, label: &str, var_1: Variable, var_2: Variable) -> Self {
+    self.jump_to(label, Instruction::je, var_1, var_2)
+  }
+
+  /// Resolve labels and create the program.
+  ///
+  /// Fails if a label is defined more than once or referenced but never
+  /// defined, if a jump can't reach its label, if a branch loop count is out
+  /// of range, or if the program doesn't fit in program memory.
+  pub fn build(mut self) -> Result<Vec<Instruction>, ProgramBuildError> {
+    if let Some(label) = self.duplicate_label {
+      return Err(ProgramBuildError::DuplicateLabel(label));
+    }
+    if self.entries.len() > MAX_INSTRUCTIONS as usize {
+      return Err(ProgramBuildError::TooManyInstructions(self.entries.len()));
+    }
+
+    let entries = std::mem::take(&mut self.entries);
+    let mut instructions = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.into_iter().enumerate() {
+      let instruction = match entry {
+        Entry::Instruction(instruction) => instruction,
+        Entry::Branch { label, loop_count } => {
+          if loop_count > MAX_LOOP_COUNT {
+            return Err(ProgramBuildError::LoopCountOutOfRange {
+              index,
+              loop_count,
+            });
+          }
+          Instruction::branch(self.resolve(label)?, loop_count)
+        }
+        Entry::BranchVars {
+          label,
+          loop_count_var,
+        } => Instruction::branch_vars(self.resolve(label)?, loop_count_var),
+        Entry::Jump {
+          label,
+          jump,
+          var_1,
+          var_2,
+        } => {
+          // Skip count is relative to the instruction following the jump.
+          let address = self.resolve(label.clone())? as usize;
+          let skip = address
+            .checked_sub(index + 1)
+            .filter(|skip| *skip <= MAX_JUMP_SKIP as usize)
+            .ok_or(ProgramBuildError::JumpOutOfRange { index, label })?;
+          jump(skip as u8, var_1, var_2)
+        }
+      };
+      instructions.push(instruction);
+    }
+
+    Ok(instructions)
+  }
+
+  fn jump_to(
+    mut self,
+    label: &str,
+    jump: fn(u8, Variable, Variable) -> Instruction,
+    var_1: Variable,
+    var_2: Variable,
+  ) -> Self {
+    self.entries.push(Entry::Jump {
+      label: label.to_string(),
+      jump,
+      var_1,
+      var_2,
+    });
+    self
+  }
+
+  fn address_of(&self, label: &str) -> Option<u8> {
+    self
+      .labels
+      .iter()
+      .find(|(name, _)| name == label)
+      .map(|(_, address)| *address)
+  }
+
+  fn resolve(&self, label: String) -> Result<u8, ProgramBuildError> {
+    self
+      .address_of(&label)
+      .ok_or(ProgramBuildError::UndefinedLabel(label))
+  }
+}
+
+/// Placement of the programs of all three engines in the shared program
+/// memory.
+///
+/// Programs are placed back to back in engine order (E1, E2, E3), so that no
+/// engine's program overruns another's; each engine's entry point is the
+/// start of its region. Each program is written as if it started at address
+/// 0 (e.g. [`blink`] and [`breathe`]); the absolute addresses of `branch`,
+/// `branch_vars` and mux instructions are relocated to the program's region.
+///
+/// Example:
+/// ```no_run
+/// # use std::time::Duration;
+/// # use ti_lp55231::{program::*, Channel, LP55231};
+/// # let mut ic = LP55231::create("/dev/i2c-2", 0x32)?;
+/// let second = Duration::from_secs(1);
+/// let layout = ProgramLayout::new([
+///   blink(Channel::D1, second, second, 255, None),
+///   breathe(Channel::D2, 2 * second, 0, 255),
+///   vec![],
+/// ])?;
+/// ic.load_program_layout(&layout)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ProgramLayout {
+  instructions: Vec<Instruction>,
+  entry_points: [u8; 3],
+  lengths: [u8; 3],
+}
+
+impl ProgramLayout {
+  /// Lay out the programs of each engine, ordered E1 through E3.
+  ///
+  /// Engines without instructions share a single `end` instruction placed
+  /// after all programs, which becomes their entry point, so that starting
+  /// them doesn't run another engine's program. Fails if the programs
+  /// (including that `end`) don't fit in program memory together, or if an
+  /// instruction addresses past the end of its own program.
+  pub fn new(
+    programs: [Vec<Instruction>; 3],
+  ) -> Result<Self, ProgramBuildError> {
+    let has_empty = programs.iter().any(Vec::is_empty);
+    let total =
+      programs.iter().map(Vec::len).sum::<usize>() + has_empty as usize;
+    if total > MAX_INSTRUCTIONS as usize {
+      return Err(ProgramBuildError::TooManyInstructions(total));
+    }
+
+    let end = (total - has_empty as usize) as u8;
+    let mut instructions = Vec::with_capacity(total);
+    let mut entry_points = [end; 3];
+    let mut lengths = [0; 3];
+    for (engine, program) in Engine::all().into_iter().zip(programs) {
+      let offset = instructions.len() as u8;
+      if !program.is_empty() {
+        entry_points[engine as usize] = offset;
+      }
+      lengths[engine as usize] = program.len() as u8;
+      for (index, instruction) in program.iter().enumerate() {
+        let relocated = relocate(instruction, offset, program.len() as u8)
+          .map_err(|address| ProgramBuildError::AddressOutOfProgram {
+            engine,
+            index,
+            address,
+          })?;
+        instructions.push(relocated);
+      }
+    }
+    if has_empty {
+      instructions.push(Instruction::end(false, false));
+    }
+
+    Ok(Self {
+      instructions,
+      entry_points,
+      lengths,
+    })
+  }
+
+  /// All instructions, in program memory order.
+  pub fn instructions(&self) -> &[Instruction] {
+    &self.instructions
+  }
+
+  /// Entry point of the specified [`Engine`].
+  pub fn entry_point(&self, engine: Engine) -> u8 {
+    self.entry_points[engine as usize]
+  }
+
+  /// Entry points of all engines, ordered E1 through E3.
+  pub fn entry_points(&self) -> [u8; 3] {
+    self.entry_points
+  }
+
+  /// Program memory addresses occupied by the program of the specified
+  /// [`Engine`]; empty if the engine has no instructions (i.e. only runs the
+  /// shared `end`).
+  pub fn engine_range(&self, engine: Engine) -> Range<u8> {
+    let start = self.entry_points[engine as usize];
+    start..start + self.lengths[engine as usize]
+  }
+
+  /// Number of program memory addresses occupied by all programs (and the
+  /// `end` shared by engines without instructions), out of
+  /// [`MAX_INSTRUCTIONS`].
+  pub fn total_used(&self) -> u8 {
+    self.instructions.len() as u8
+  }
+}
+
+/// Reasons a [`ProgramBuilder`] or [`ProgramLayout`] can fail to build a
+/// program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProgramBuildError {
+  /// Label is referenced but never defined.
+  UndefinedLabel(String),
+  /// Label is defined more than once.
+  DuplicateLabel(String),
+  /// Jump at `index` can't reach `label`, either because it's defined before
+  /// the jump or it's more than [`MAX_JUMP_SKIP`] instructions ahead.
+  JumpOutOfRange { index: usize, label: String },
+  /// Branch at `index` has a `loop_count` over [`MAX_LOOP_COUNT`], which
+  /// doesn't fit the instruction.
+  LoopCountOutOfRange { index: usize, loop_count: u8 },
+  /// Program (or all programs of a layout, including their shared `end`) has
+  /// more than [`MAX_INSTRUCTIONS`] instructions.
+  TooManyInstructions(usize),
+  /// Branch or mux instruction at `index` of the program of `engine` in a
+  /// [`ProgramLayout`] refers to an `address` past the end of that program,
+  /// so it can't be relocated.
+  AddressOutOfProgram {
+    engine: Engine,
+    index: usize,
+    address: u8,
+  },
+}
+
+impl fmt::Display for ProgramBuildError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UndefinedLabel(label) => write!(f, "undefined label {:?}", label),
+      Self::DuplicateLabel(label) => write!(f, "duplicate label {:?}", label),
+      Self::JumpOutOfRange { index, label } => write!(
+        f,
+        "jump at instruction {} can't reach label {:?}; must be at most {} instructions ahead",
+        index, label, MAX_JUMP_SKIP
+      ),
+      Self::LoopCountOutOfRange { index, loop_count } => write!(
+        f,
+        "invalid loop count ({}) for branch at instruction {}; must be in range [0:{}]",
+        loop_count, index, MAX_LOOP_COUNT
+      ),
+      Self::TooManyInstructions(count) => write!(
+        f,
+        "too many instructions ({}); limit is {}",
+        count, MAX_INSTRUCTIONS
+      ),
+      Self::AddressOutOfProgram {
+        engine,
+        index,
+        address,
+      } => write!(
+        f,
+        "instruction {} of engine {:?} refers to address {} past the end of its program",
+        index, engine, address
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ProgramBuildError {}
+
+// Move the absolute address operand of `instruction`, if any, by `offset`;
+// fails with the address if it's past the end of a program of `len`
+// instructions.
+fn relocate(
+  instruction: &Instruction,
+  offset: u8,
+  len: u8,
+) -> Result<Instruction, u8> {
+  use crate::DecodedInstruction::*;
+
+  let moved = |address: u8| {
+    if address < len {
+      Ok(address + offset)
+    } else {
+      Err(address)
+    }
+  };
+  let relocated = match instruction.decode() {
+    Branch {
+      step_number,
+      loop_count,
+    } => Instruction::branch(moved(step_number)?, loop_count),
+    BranchVars {
+      step_number,
+      loop_count_var,
+    } => Instruction::branch_vars(moved(step_number)?, loop_count_var),
+    MuxLdStart(address) => Instruction::mux_ld_start(moved(address)?),
+    MuxMapStart(address) => Instruction::mux_map_start(moved(address)?),
+    MuxLdEnd(address) => Instruction::mux_ld_end(moved(address)?),
+    MuxLdAddr(address) => Instruction::mux_ld_addr(moved(address)?),
+    MuxMapAddr(address) => Instruction::mux_map_addr(moved(address)?),
+    _ => *instruction,
+  };
+  Ok(relocated)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::DecodedInstruction;
+
+  const VARIABLES: [Variable; 4] =
+    [Variable::A, Variable::B, Variable::C, Variable::D];
+
+  #[test]
+  fn ramp_from_vars_round_trips_through_decode() {
+    for cycle_time in [PreScale::CT0_488, PreScale::CT15_625] {
+      for direction in [Direction::Up, Direction::Down] {
+        for step_time_var in VARIABLES {
+          for increments_var in VARIABLES {
+            let instruction = Instruction::from(RampFromVars {
+              cycle_time,
+              direction,
+              step_time_var,
+              increments_var,
+            });
+
+            // Shares its encoding with set_pwm_from_var, which takes
+            // precedence.
+            let expected = if cycle_time == PreScale::CT15_625
+              && direction == Direction::Down
+              && step_time_var == Variable::A
+            {
+              DecodedInstruction::SetPwmFromVar(increments_var)
+            } else {
+              DecodedInstruction::RampFromVars {
+                cycle_time,
+                direction,
+                step_time_var,
+                increments_var,
+              }
+            };
+            assert_eq!(instruction.decode(), expected);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn ramp_from_vars_encodes_direction_bit() {
+    // Spec: sign bit 0 increments, 1 decrements.
+    let up = Instruction::ramp_from_vars(false, true, Variable::B, Variable::C);
+    let down =
+      Instruction::ramp_from_vars(false, false, Variable::B, Variable::C);
+
+    assert_eq!(up.as_u16(), 0b1000_0100_0000_0110);
+    assert_eq!(down.as_u16(), 0b1000_0100_0010_0110);
+    assert_eq!(
+      up,
+      Instruction::increment_pwm_from_var(Variable::B, Variable::C)
+    );
+    assert_eq!(
+      down,
+      Instruction::decrement_pwm_from_var(Variable::B, Variable::C)
+    );
+  }
+
+  #[test]
+  fn layout_relocates_absolute_addresses() {
+    let first = vec![Instruction::NOP; 4];
+    let second = vec![
+      Instruction::mux_map_start(0),
+      Instruction::mux_ld_end(1),
+      Instruction::set_pwm(255),
+      Instruction::branch(2, 0),
+    ];
+    let layout = ProgramLayout::new([first, second, vec![]]).unwrap();
+
+    assert_eq!(layout.entry_point(Engine::E2), 4);
+    assert_eq!(
+      &layout.instructions()[4..8],
+      &[
+        Instruction::mux_map_start(4),
+        Instruction::mux_ld_end(5),
+        Instruction::set_pwm(255),
+        Instruction::branch(6, 0),
+      ]
+    );
+  }
+
+  #[test]
+  fn layout_rejects_addresses_past_program_end() {
+    let result =
+      ProgramLayout::new([vec![], vec![Instruction::branch(1, 0)], vec![]]);
+
+    assert_eq!(
+      result.err(),
+      Some(ProgramBuildError::AddressOutOfProgram {
+        engine: Engine::E2,
+        index: 0,
+        address: 1,
+      })
+    );
+  }
+
+  #[test]
+  fn layout_points_empty_engines_at_shared_end() {
+    let program = vec![Instruction::set_pwm(255), Instruction::NOP];
+    let layout = ProgramLayout::new([vec![], program.clone(), vec![]]).unwrap();
+
+    assert_eq!(layout.entry_points(), [2, 0, 2]);
+    assert_eq!(layout.instructions()[..2], program);
+    assert_eq!(layout.instructions()[2], Instruction::end(false, false));
+    assert_eq!(layout.engine_range(Engine::E1), 2..2);
+    assert_eq!(layout.engine_range(Engine::E2), 0..2);
+    assert_eq!(layout.total_used(), 3);
+  }
+
+  #[test]
+  fn layout_without_empty_engines_has_no_shared_end() {
+    let program = vec![Instruction::NOP];
+    let layout =
+      ProgramLayout::new([program.clone(), program.clone(), program]).unwrap();
+
+    assert_eq!(layout.entry_points(), [0, 1, 2]);
+    assert_eq!(layout.total_used(), 3);
+  }
+
+  #[test]
+  fn layout_counts_shared_end_against_capacity() {
+    let program = vec![Instruction::NOP; MAX_INSTRUCTIONS as usize];
+    let result = ProgramLayout::new([program, vec![], vec![]]);
+
+    assert_eq!(
+      result.err(),
+      Some(ProgramBuildError::TooManyInstructions(
+        MAX_INSTRUCTIONS as usize + 1
+      ))
+    );
+  }
+
+  #[test]
+  fn builder_accepts_max_loop_count() {
+    let program = ProgramBuilder::new()
+      .label("loop")
+      .push(Instruction::NOP)
+      .branch_to("loop", MAX_LOOP_COUNT)
+      .build()
+      .unwrap();
+
+    assert_eq!(program[1], Instruction::branch(0, MAX_LOOP_COUNT));
+  }
+
+  #[test]
+  fn builder_rejects_loop_count_out_of_range() {
+    let result = ProgramBuilder::new()
+      .label("loop")
+      .push(Instruction::NOP)
+      .branch_to("loop", MAX_LOOP_COUNT + 1)
+      .build();
+
+    assert_eq!(
+      result,
+      Err(ProgramBuildError::LoopCountOutOfRange {
+        index: 1,
+        loop_count: 64,
+      })
+    );
+  }
+}
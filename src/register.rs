@@ -1,4 +1,9 @@
-use crate::types::{Channel, Engine, Fader};
+use std::fmt;
+
+use crate::{
+  types::{Channel, Engine, Fader},
+  Variable,
+};
 
 // I2C registers.
 #[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
@@ -69,7 +74,157 @@ pub enum Register {
   PROG_MEM_BASE = 0x50,
 }
 
+impl fmt::Display for Register {
+  /// Formats as name and address, e.g. `MISC (0x36)`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} (0x{:02x})", self, self.address())
+  }
+}
+
 impl Register {
+  /// Address of the register.
+  pub fn address(&self) -> u8 {
+    *self as u8
+  }
+
+  /// Every register, sorted by address.
+  pub const ALL: &'static [Register] = &[
+    Register::ENABLE_ENGINE_CNTRL1,
+    Register::ENGINE_CNTRL_2,
+    Register::OUTPUT_DIRECT_RATIOMETRIC_MSB,
+    Register::OUTPUT_DIRECT_RATIOMETRIC_LSB,
+    Register::OUTPUT_ON_OFF_CONTROL_MSB,
+    Register::OUTPUT_ON_OFF_CONTROL_LSB,
+    Register::D1_CONTROL,
+    Register::D2_CONTROL,
+    Register::D3_CONTROL,
+    Register::D4_CONTROL,
+    Register::D5_CONTROL,
+    Register::D6_CONTROL,
+    Register::D7_CONTROL,
+    Register::D8_CONTROL,
+    Register::D9_CONTROL,
+    Register::D1_PWM,
+    Register::D2_PWM,
+    Register::D3_PWM,
+    Register::D4_PWM,
+    Register::D5_PWM,
+    Register::D6_PWM,
+    Register::D7_PWM,
+    Register::D8_PWM,
+    Register::D9_PWM,
+    Register::D1_CURRENT_CONTROL,
+    Register::D2_CURRENT_CONTROL,
+    Register::D3_CURRENT_CONTROL,
+    Register::D4_CURRENT_CONTROL,
+    Register::D5_CURRENT_CONTROL,
+    Register::D6_CURRENT_CONTROL,
+    Register::D7_CURRENT_CONTROL,
+    Register::D8_CURRENT_CONTROL,
+    Register::D9_CURRENT_CONTROL,
+    Register::MISC,
+    Register::ENGINE1_PC,
+    Register::ENGINE2_PC,
+    Register::ENGINE3_PC,
+    Register::STATUS_INTERRUPT,
+    Register::INT_GPO,
+    Register::VARIABLE,
+    Register::RESET,
+    Register::TEMP_ADC_CONTROL,
+    Register::TEMPERATURE_READ,
+    Register::TEMPERATURE_WRITE,
+    Register::LED_TEST_CONTROL,
+    Register::LED_TEST_ADC,
+    Register::ENGINE1_VARIABLE_A,
+    Register::ENGINE1_VARIABLE_B,
+    Register::ENGINE1_VARIABLE_C,
+    Register::MASTER_FADER1,
+    Register::MASTER_FADER2,
+    Register::MASTER_FADER3,
+    Register::ENG1_PROG_START_ADDR,
+    Register::ENG2_PROG_START_ADDR,
+    Register::ENG3_PROG_START_ADDR,
+    Register::PROG_MEM_PAGE_SEL,
+    Register::PROG_MEM_BASE,
+  ];
+
+  /// Look up the register at the specified address.
+  ///
+  /// Returns `None` for reserved addresses and program memory addresses past
+  /// `PROG_MEM_BASE`.
+  pub fn from_address(address: u8) -> Option<Register> {
+    Register::ALL.iter().find(|r| **r as u8 == address).copied()
+  }
+
+  /// Whether `address` is in one of the reserved ranges of the register map,
+  /// i.e. neither a [`Register`] nor program memory (`PROG_MEM_BASE` through
+  /// `0x6F`).
+  pub fn is_reserved(address: u8) -> bool {
+    let program_memory = Register::PROG_MEM_BASE as u8..=0x6F;
+    Register::from_address(address).is_none()
+      && !program_memory.contains(&address)
+  }
+
+  /// Whether the register can be read back as a control register.
+  ///
+  /// `RESET` is write-only, and `PROG_MEM_BASE` is the first byte of the
+  /// selected program memory page rather than a control register (see
+  /// [`LP55231::read_program_page`](crate::LP55231::read_program_page)).
+  pub fn is_readable(&self) -> bool {
+    !matches!(self, Register::RESET | Register::PROG_MEM_BASE)
+  }
+
+  /// Whether a value written to the register can be expected to read back
+  /// unchanged, i.e. whether [read-after-write
+  /// verification](crate::LP55231::verify_writes) applies.
+  ///
+  /// Not the case for:
+  /// - `RESET`, which is write-only
+  /// - `STATUS/INTERRUPT`, `TEMPERATURE READ` and `LED TEST ADC`, which
+  ///   reflect the state of the IC
+  /// - `TEMP ADC CONTROL`, whose `TEMP_MEAS_BUSY` bit is set by the IC
+  pub fn is_verifiable(&self) -> bool {
+    !matches!(
+      self,
+      Register::RESET
+        | Register::STATUS_INTERRUPT
+        | Register::TEMPERATURE_READ
+        | Register::LED_TEST_ADC
+        | Register::TEMP_ADC_CONTROL
+    )
+  }
+
+  /// Value of the register after a [reset](crate::LP55231::reset) or power
+  /// up, e.g. to verify a reset took effect.
+  ///
+  /// Returns `None` for registers without a fixed default: `RESET`
+  /// (write-only), `STATUS/INTERRUPT`, `TEMPERATURE READ` and `LED TEST ADC`
+  /// (reflecting the state of the IC), and program memory.
+  pub const fn default_value(&self) -> Option<u8> {
+    match self {
+      Register::OUTPUT_ON_OFF_CONTROL_MSB => Some(0b0000_0001),
+      Register::OUTPUT_ON_OFF_CONTROL_LSB => Some(0b1111_1111),
+      // 17.5mA
+      Register::D1_CURRENT_CONTROL
+      | Register::D2_CURRENT_CONTROL
+      | Register::D3_CURRENT_CONTROL
+      | Register::D4_CURRENT_CONTROL
+      | Register::D5_CURRENT_CONTROL
+      | Register::D6_CURRENT_CONTROL
+      | Register::D7_CURRENT_CONTROL
+      | Register::D8_CURRENT_CONTROL
+      | Register::D9_CURRENT_CONTROL => Some(0b1010_1111),
+      Register::ENG2_PROG_START_ADDR => Some(8),
+      Register::ENG3_PROG_START_ADDR => Some(16),
+      Register::RESET
+      | Register::STATUS_INTERRUPT
+      | Register::TEMPERATURE_READ
+      | Register::LED_TEST_ADC
+      | Register::PROG_MEM_BASE => None,
+      _ => Some(0),
+    }
+  }
+
   pub fn control_for(channel: Channel) -> Register {
     match channel {
       Channel::D1 => Register::D1_CONTROL,
@@ -135,4 +290,18 @@ impl Register {
       Engine::E3 => Register::ENGINE3_PC,
     }
   }
+
+  /// Register holding the value of the specified engine [`Variable`], if the
+  /// combination is readable.
+  ///
+  /// Only engine 1 exposes its local variables; variable D is global.
+  pub fn variable_for(engine: Engine, var: Variable) -> Option<Register> {
+    match (engine, var) {
+      (Engine::E1, Variable::A) => Some(Register::ENGINE1_VARIABLE_A),
+      (Engine::E1, Variable::B) => Some(Register::ENGINE1_VARIABLE_B),
+      (Engine::E1, Variable::C) => Some(Register::ENGINE1_VARIABLE_C),
+      (_, Variable::D) => Some(Register::VARIABLE),
+      _ => None,
+    }
+  }
 }
@@ -0,0 +1,531 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::{DecodedInstruction, Direction, Instruction, PreScale, Variable};
+
+/// Software model of a single programming engine, for developing programs
+/// without hardware.
+///
+/// Executes one instruction per [`Self::step`], reporting the outcome of each
+/// as a [`SimulatorStep`]. Models:
+/// - ramp, set_pwm and wait instructions (including variable forms), and the
+///   time they take
+/// - branch, conditional jump, int, end and rst instructions
+/// - ld, add and sub instructions
+///
+/// Mux instructions are not modeled (i.e. the engine PWM is reported, rather
+/// than the PWM of each mapped [`Channel`](crate::Channel)), and instructions
+/// other than ramp and wait take no time.
+///
+/// Example:
+/// ```
+/// # use ti_lp55231::{Direction, Instruction, PreScale, Simulator};
+/// let mut simulator = Simulator::new(vec![
+///   Instruction::set_pwm(0),
+///   Instruction::ramp(PreScale::CT0_488, 1, Direction::Up, 255),
+///   Instruction::end(false, false),
+/// ]);
+/// let steps = simulator.run(10);
+/// assert_eq!(steps.last().unwrap().pwm, 255);
+/// ```
+pub struct Simulator {
+  program: Vec<Instruction>,
+  pc: u8,
+  pwm: u8,
+  variables: [u8; 4],
+  // Remaining iterations for each active branch, keyed by branch address.
+  loop_counters: HashMap<u8, u8>,
+  elapsed: Duration,
+  halted: bool,
+}
+
+/// Outcome of executing a single instruction in the [`Simulator`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SimulatorStep {
+  /// Address of the executed instruction.
+  pub pc: u8,
+  /// The executed instruction.
+  pub instruction: DecodedInstruction,
+  /// Engine PWM value after executing the instruction.
+  pub pwm: u8,
+  /// Variables A through D after executing the instruction.
+  pub variables: [u8; 4],
+  /// Whether a branch or conditional jump was taken; `None` for other
+  /// instructions.
+  pub branch_taken: Option<bool>,
+  /// Whether the instruction raised an interrupt.
+  pub interrupt: bool,
+  /// Simulated time at which the instruction started executing.
+  pub start: Duration,
+  /// Time the instruction took to execute.
+  pub duration: Duration,
+}
+
+impl Simulator {
+  /// Create a simulator for the specified program, starting at address 0.
+  pub fn new(program: Vec<Instruction>) -> Self {
+    Self {
+      program,
+      pc: 0,
+      pwm: 0,
+      variables: [0; 4],
+      loop_counters: HashMap::new(),
+      elapsed: Duration::ZERO,
+      halted: false,
+    }
+  }
+
+  /// Set the value of a variable, e.g. to emulate the host changing the
+  /// global variable D.
+  pub fn set_variable(&mut self, var: Variable, value: u8) {
+    self.variables[var as usize] = value;
+  }
+
+  /// Value of a variable.
+  pub fn variable(&self, var: Variable) -> u8 {
+    self.variables[var as usize]
+  }
+
+  /// Current program counter.
+  pub fn pc(&self) -> u8 {
+    self.pc
+  }
+
+  /// Current engine PWM value.
+  pub fn pwm(&self) -> u8 {
+    self.pwm
+  }
+
+  /// Total simulated time.
+  pub fn elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  /// Whether the program finished, by executing an `end` instruction or
+  /// running past its last instruction.
+  pub fn is_halted(&self) -> bool {
+    self.halted
+  }
+
+  /// Execute up to `max_steps` instructions, stopping early if the program
+  /// finishes.
+  pub fn run(&mut self, max_steps: usize) -> Vec<SimulatorStep> {
+    let mut steps = vec![];
+    while steps.len() < max_steps {
+      match self.step() {
+        Some(step) => steps.push(step),
+        None => break,
+      }
+    }
+    steps
+  }
+
+  /// Execute the instruction at the program counter.
+  ///
+  /// Returns `None` once the program finished.
+  pub fn step(&mut self) -> Option<SimulatorStep> {
+    use DecodedInstruction::*;
+
+    if self.halted {
+      return None;
+    }
+    let Some(instruction) = self.program.get(self.pc as usize) else {
+      self.halted = true;
+      return None;
+    };
+
+    let pc = self.pc;
+    let decoded = instruction.decode();
+    let mut next_pc = pc.wrapping_add(1);
+    let mut branch_taken = None;
+    let mut interrupt = false;
+    let mut duration = Duration::ZERO;
+
+    match decoded {
+      Ramp {
+        cycle_time,
+        cycles_per_step,
+        direction,
+        number_of_steps,
+      } => {
+        duration =
+          self.ramp(cycle_time, cycles_per_step, direction, number_of_steps)
+      }
+      RampFromVars {
+        cycle_time,
+        direction,
+        step_time_var,
+        increments_var,
+      } => {
+        let cycles_per_step = self.variable(step_time_var);
+        let number_of_steps = self.variable(increments_var);
+        duration =
+          self.ramp(cycle_time, cycles_per_step, direction, number_of_steps)
+      }
+      SetPwm(value) => self.pwm = value,
+      SetPwmFromVar(var) => self.pwm = self.variable(var),
+      Wait { cycle_time, cycles } => {
+        duration = cycle_time.step_time() * cycles as u32
+      }
+      Rst => next_pc = 0,
+      Branch {
+        step_number,
+        loop_count,
+      } => {
+        let taken = self.branch(pc, loop_count);
+        if taken {
+          next_pc = step_number;
+        }
+        branch_taken = Some(taken);
+      }
+      BranchVars {
+        step_number,
+        loop_count_var,
+      } => {
+        let taken = self.branch(pc, self.variable(loop_count_var));
+        if taken {
+          next_pc = step_number;
+        }
+        branch_taken = Some(taken);
+      }
+      Int => interrupt = true,
+      End {
+        interrupt: end_interrupt,
+        reset_program_counter,
+      } => {
+        interrupt = end_interrupt;
+        if reset_program_counter {
+          next_pc = 0;
+        }
+        self.halted = true;
+      }
+      Jne {
+        num_instructions_to_skip,
+        var_1,
+        var_2,
+      } => {
+        let taken = self.variable(var_1) != self.variable(var_2);
+        if taken {
+          next_pc = next_pc.wrapping_add(num_instructions_to_skip);
+        }
+        branch_taken = Some(taken);
+      }
+      Jl {
+        num_instructions_to_skip,
+        var_1,
+        var_2,
+      } => {
+        let taken = self.variable(var_1) < self.variable(var_2);
+        if taken {
+          next_pc = next_pc.wrapping_add(num_instructions_to_skip);
+        }
+        branch_taken = Some(taken);
+      }
+      Jge {
+        num_instructions_to_skip,
+        var_1,
+        var_2,
+      } => {
+        let taken = self.variable(var_1) >= self.variable(var_2);
+        if taken {
+          next_pc = next_pc.wrapping_add(num_instructions_to_skip);
+        }
+        branch_taken = Some(taken);
+      }
+      Je {
+        num_instructions_to_skip,
+        var_1,
+        var_2,
+      } => {
+        let taken = self.variable(var_1) == self.variable(var_2);
+        if taken {
+          next_pc = next_pc.wrapping_add(num_instructions_to_skip);
+        }
+        branch_taken = Some(taken);
+      }
+      Ld { target_var, value } => self.set_variable(target_var, value),
+      AddNumerical { target_var, value } => {
+        let result = self.variable(target_var).wrapping_add(value);
+        self.set_variable(target_var, result);
+      }
+      AddVars {
+        target_var,
+        var_1,
+        var_2,
+      } => {
+        let result = self.variable(var_1).wrapping_add(self.variable(var_2));
+        self.set_variable(target_var, result);
+      }
+      SubNumerical { target_var, value } => {
+        let result = self.variable(target_var).wrapping_sub(value);
+        self.set_variable(target_var, result);
+      }
+      SubVars {
+        target_var,
+        var_1,
+        var_2,
+      } => {
+        let result = self.variable(var_1).wrapping_sub(self.variable(var_2));
+        self.set_variable(target_var, result);
+      }
+      // Mux instructions aren't modeled; unknown words are skipped.
+      _ => {}
+    }
+
+    let step = SimulatorStep {
+      pc,
+      instruction: decoded,
+      pwm: self.pwm,
+      variables: self.variables,
+      branch_taken,
+      interrupt,
+      start: self.elapsed,
+      duration,
+    };
+
+    self.pc = next_pc;
+    self.elapsed += duration;
+
+    Some(step)
+  }
+
+  // Apply a ramp to the PWM value, returning how long it takes.
+  fn ramp(
+    &mut self,
+    cycle_time: PreScale,
+    cycles_per_step: u8,
+    direction: Direction,
+    number_of_steps: u8,
+  ) -> Duration {
+    self.pwm = match direction {
+      Direction::Up => self.pwm.saturating_add(number_of_steps),
+      Direction::Down => self.pwm.saturating_sub(number_of_steps),
+    };
+
+    cycle_time.step_time() * cycles_per_step as u32 * number_of_steps as u32
+  }
+
+  // Whether the branch at `pc` loops back, given its loop count (0 loops
+  // forever). Once a branch finishes looping its counter resets, so it can be
+  // nested in an outer loop.
+  fn branch(&mut self, pc: u8, loop_count: u8) -> bool {
+    if loop_count == 0 {
+      return true;
+    }
+
+    let remaining = self.loop_counters.entry(pc).or_insert(loop_count);
+    if *remaining == 0 {
+      self.loop_counters.remove(&pc);
+      return false;
+    }
+    *remaining -= 1;
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use Variable::*;
+
+  const FAST: Duration = Duration::from_nanos(488_281);
+  const SLOW: Duration = Duration::from_micros(15_625);
+
+  #[test]
+  fn ramps_follow_pwm_trajectory_and_timing() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::set_pwm(10),
+      Instruction::ramp(PreScale::CT0_488, 2, Direction::Up, 100),
+      Instruction::wait(PreScale::CT15_625, 4),
+      Instruction::ramp(PreScale::CT15_625, 1, Direction::Down, 50),
+      Instruction::ramp(PreScale::CT15_625, 1, Direction::Down, 255),
+      Instruction::end(false, false),
+    ]);
+
+    let steps = simulator.run(10);
+
+    let pwm: Vec<_> = steps.iter().map(|step| step.pwm).collect();
+    assert_eq!(pwm, [10, 110, 110, 60, 0, 0]);
+    let durations: Vec<_> = steps.iter().map(|step| step.duration).collect();
+    assert_eq!(
+      durations,
+      [
+        Duration::ZERO,
+        FAST * 2 * 100,
+        SLOW * 4,
+        SLOW * 50,
+        SLOW * 255,
+        Duration::ZERO
+      ]
+    );
+    for pair in steps.windows(2) {
+      assert_eq!(pair[1].start, pair[0].start + pair[0].duration);
+    }
+    assert_eq!(simulator.elapsed(), durations.iter().sum());
+  }
+
+  #[test]
+  fn ramp_from_vars_reads_step_time_and_increments() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::ld(A, 3),
+      Instruction::ld(B, 20),
+      Instruction::ramp_from_vars(false, true, A, B),
+    ]);
+
+    let step = simulator.run(3)[2];
+
+    assert_eq!(step.pwm, 20);
+    assert_eq!(step.duration, FAST * 3 * 20);
+  }
+
+  #[test]
+  fn branch_loops_loop_count_times() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::ld(A, 0),
+      Instruction::add_numerical(A, 1),
+      Instruction::branch(1, 3),
+      Instruction::end(false, false),
+    ]);
+
+    let steps = simulator.run(20);
+
+    assert_eq!(simulator.variable(A), 4);
+    let taken: Vec<_> =
+      steps.iter().filter_map(|step| step.branch_taken).collect();
+    assert_eq!(taken, [true, true, true, false]);
+    assert!(simulator.is_halted());
+  }
+
+  #[test]
+  fn nested_branch_restarts_its_count() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::add_numerical(A, 1),
+      Instruction::branch(0, 1),
+      Instruction::branch(0, 2),
+      Instruction::end(false, false),
+    ]);
+
+    simulator.run(50);
+
+    // Inner loop runs the body twice per pass; outer loop makes three passes.
+    assert_eq!(simulator.variable(A), 6);
+    assert!(simulator.is_halted());
+  }
+
+  #[test]
+  fn branch_with_zero_loop_count_loops_forever() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::add_numerical(A, 1),
+      Instruction::branch(0, 0),
+    ]);
+
+    let steps = simulator.run(100);
+
+    assert_eq!(steps.len(), 100);
+    assert_eq!(simulator.variable(A), 50);
+    assert!(!simulator.is_halted());
+  }
+
+  #[test]
+  fn branch_vars_reads_loop_count() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::ld(B, 2),
+      Instruction::add_numerical(A, 1),
+      Instruction::branch_vars(1, B),
+      Instruction::end(false, false),
+    ]);
+
+    simulator.run(20);
+
+    assert_eq!(simulator.variable(A), 3);
+  }
+
+  #[test]
+  fn conditional_jumps_skip_when_condition_holds() {
+    type Jump = fn(u8, Variable, Variable) -> Instruction;
+    let cases: [(Jump, u8, u8, bool); 12] = [
+      (Instruction::jne, 1, 2, true),
+      (Instruction::jne, 2, 2, false),
+      (Instruction::jl, 1, 2, true),
+      (Instruction::jl, 2, 2, false),
+      (Instruction::jl, 3, 2, false),
+      (Instruction::jge, 3, 2, true),
+      (Instruction::jge, 2, 2, true),
+      (Instruction::jge, 1, 2, false),
+      (Instruction::je, 2, 2, true),
+      (Instruction::je, 1, 2, false),
+      (Instruction::je, 3, 2, false),
+      (Instruction::jne, 3, 2, true),
+    ];
+
+    for (jump, a, b, taken) in cases {
+      let mut simulator = Simulator::new(vec![
+        Instruction::ld(A, a),
+        Instruction::ld(B, b),
+        jump(2, A, B),
+        Instruction::set_pwm(1),
+        Instruction::set_pwm(2),
+        Instruction::set_pwm(3),
+        Instruction::end(false, false),
+      ]);
+
+      let steps = simulator.run(10);
+
+      let jump = &steps[2];
+      assert_eq!(jump.branch_taken, Some(taken), "{:?}", jump.instruction);
+      let pcs: Vec<_> = steps.iter().map(|step| step.pc).collect();
+      match taken {
+        true => assert_eq!(pcs, [0, 1, 2, 5, 6]),
+        false => assert_eq!(pcs, [0, 1, 2, 3, 4, 5, 6]),
+      }
+      assert_eq!(simulator.pwm(), 3);
+    }
+  }
+
+  #[test]
+  fn end_halts_without_interrupt_or_reset() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::set_pwm(255),
+      Instruction::end(false, false),
+      Instruction::set_pwm(0),
+    ]);
+
+    let steps = simulator.run(10);
+
+    assert_eq!(steps.len(), 2);
+    assert!(!steps[1].interrupt);
+    assert!(simulator.is_halted());
+    assert_eq!(simulator.pc(), 2);
+    assert_eq!(simulator.pwm(), 255);
+    assert_eq!(simulator.step(), None);
+  }
+
+  #[test]
+  fn end_raises_interrupt_and_resets_program_counter() {
+    let mut simulator = Simulator::new(vec![
+      Instruction::set_pwm(255),
+      Instruction::end(true, true),
+    ]);
+
+    let steps = simulator.run(10);
+
+    assert_eq!(steps.len(), 2);
+    assert!(steps[1].interrupt);
+    assert_eq!(
+      steps[1].instruction,
+      DecodedInstruction::End {
+        interrupt: true,
+        reset_program_counter: true
+      }
+    );
+    assert!(simulator.is_halted());
+    assert_eq!(simulator.pc(), 0);
+  }
+
+  #[test]
+  fn running_past_last_instruction_halts() {
+    let mut simulator = Simulator::new(vec![Instruction::set_pwm(1)]);
+
+    assert_eq!(simulator.run(10).len(), 1);
+    assert!(simulator.is_halted());
+  }
+}
@@ -0,0 +1,259 @@
+//! Utilities for testing code using the driver without hardware.
+//!
+//! Requires the `mock` feature.
+
+use linux_embedded_hal::i2cdev::{core::I2CDevice, linux::LinuxI2CError};
+
+use crate::{Mask, Register, INSTRUCTIONS_PER_PAGE, MAX_PAGES};
+
+const PAGE_SIZE: usize = INSTRUCTIONS_PER_PAGE as usize * 2;
+
+/// In-memory I2C device emulating the LP55231 register file.
+///
+/// Records every register write, and lets tests preload register values
+/// before handing the device to [`LP55231::new`](crate::LP55231::new).
+///
+/// Models:
+/// - Register address auto-increment, only when `EN_AUTO_INCR` is set in
+///   `MISC` (otherwise consecutive bytes hit the same register)
+/// - Program memory paging via `PROG MEM PAGE SEL`
+/// - Engine interrupt flags in `STATUS/INTERRUPT`, cleared when read
+/// - Bus errors, on demand (see [`Self::fail_next`])
+///
+/// Does *not* model any other chip behavior (e.g. engines don't run, `RESET`
+/// doesn't reset registers).
+///
+/// Example:
+/// ```
+/// # use ti_lp55231::{test_util::MockI2c, Register, LP55231};
+/// let mut ic = LP55231::new(MockI2c::new());
+/// ic.set_enabled(true)?;
+/// ic.device().expect_write(Register::ENABLE_ENGINE_CNTRL1, 0b0100_0000);
+/// # Ok::<(), linux_embedded_hal::i2cdev::linux::LinuxI2CError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct MockI2c {
+  registers: [u8; Register::PROG_MEM_BASE as usize],
+  program: [[u8; PAGE_SIZE]; MAX_PAGES as usize],
+  address: u8,
+  writes: Vec<(u8, u8)>,
+  // Errno and number of upcoming transfers to fail with it.
+  fail_next: Option<(i32, usize)>,
+  failures: usize,
+}
+
+impl Default for MockI2c {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MockI2c {
+  /// Create a device with all registers and program memory cleared.
+  pub fn new() -> Self {
+    Self {
+      registers: [0; Register::PROG_MEM_BASE as usize],
+      program: [[0; PAGE_SIZE]; MAX_PAGES as usize],
+      address: 0,
+      writes: vec![],
+      fail_next: None,
+      failures: 0,
+    }
+  }
+
+  /// Fail the next `count` transfers with the specified Linux `errno`, as
+  /// the I2C driver would (e.g. `EIO` for a NAK), to exercise retries.
+  pub fn fail_next(&mut self, count: usize, errno: i32) {
+    self.fail_next = Some((errno, count));
+  }
+
+  /// Number of transfers failed so far (see [`Self::fail_next`]).
+  pub fn failures(&self) -> usize {
+    self.failures
+  }
+
+  /// Preload a register value, without recording it as a write.
+  pub fn set_register(&mut self, register: Register, value: u8) {
+    self.poke(register as u8, value);
+  }
+
+  /// Current value of a register.
+  pub fn register(&self, register: Register) -> u8 {
+    self.peek(register as u8)
+  }
+
+  /// Current contents of a program memory page.
+  pub fn program_page(&self, page: u8) -> &[u8] {
+    &self.program[page as usize]
+  }
+
+  /// Every write, in order, as address and value pairs.
+  pub fn writes(&self) -> &[(u8, u8)] {
+    &self.writes
+  }
+
+  /// Forget all recorded writes.
+  pub fn clear_writes(&mut self) {
+    self.writes.clear();
+  }
+
+  /// Panic unless `value` was written to `register`.
+  pub fn expect_write(&self, register: Register, value: u8) {
+    let write = (register as u8, value);
+    if !self.writes.contains(&write) {
+      panic!(
+        "expected write of {:08b} to {:02x} {:?}; writes were {:02x?}",
+        value, register as u8, register, self.writes
+      );
+    }
+  }
+
+  /// Panic if anything was written to `register`.
+  pub fn expect_no_write(&self, register: Register) {
+    if self
+      .writes
+      .iter()
+      .any(|(address, _)| *address == register as u8)
+    {
+      panic!(
+        "expected no writes to {:02x} {:?}; writes were {:02x?}",
+        register as u8, register, self.writes
+      );
+    }
+  }
+
+  fn peek(&self, address: u8) -> u8 {
+    match self.program_offset(address) {
+      Some(offset) => self.program[self.page()][offset],
+      None => self.registers[address as usize],
+    }
+  }
+
+  fn poke(&mut self, address: u8, value: u8) {
+    match self.program_offset(address) {
+      Some(offset) => {
+        let page = self.page();
+        self.program[page][offset] = value;
+      }
+      None => self.registers[address as usize] = value,
+    }
+  }
+
+  fn program_offset(&self, address: u8) -> Option<usize> {
+    let offset =
+      (address as usize).checked_sub(Register::PROG_MEM_BASE as usize)?;
+    Some(offset % PAGE_SIZE)
+  }
+
+  fn page(&self) -> usize {
+    let page = Mask::PAGE_SEL.value(self.register(Register::PROG_MEM_PAGE_SEL));
+    page as usize % MAX_PAGES as usize
+  }
+
+  fn inject_failure(&mut self) -> Result<(), LinuxI2CError> {
+    match self.fail_next {
+      Some((errno, count)) if count > 0 => {
+        self.fail_next = Some((errno, count - 1));
+        self.failures += 1;
+        Err(LinuxI2CError::Io(std::io::Error::from_raw_os_error(errno)))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  fn advance(&mut self) {
+    let misc = self.registers[Register::MISC as usize];
+    if Mask::EN_AUTO_INCR.is_set(misc) {
+      self.address = self.address.wrapping_add(1);
+    }
+  }
+}
+
+impl I2CDevice for MockI2c {
+  type Error = LinuxI2CError;
+
+  fn read(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+    self.inject_failure()?;
+    for byte in data.iter_mut() {
+      *byte = self.peek(self.address);
+      if self.address == Register::STATUS_INTERRUPT as u8 {
+        let interrupts =
+          Mask::ENG1_INT.bits() | Mask::ENG2_INT.bits() | Mask::ENG3_INT.bits();
+        self.poke(self.address, *byte & !interrupts);
+      }
+      self.advance();
+    }
+    Ok(())
+  }
+
+  fn write(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+    self.inject_failure()?;
+    let Some((address, values)) = data.split_first() else {
+      return Ok(());
+    };
+
+    self.address = *address;
+    for value in values {
+      self.writes.push((self.address, *value));
+      self.poke(self.address, *value);
+      self.advance();
+    }
+    Ok(())
+  }
+
+  fn smbus_write_quick(&mut self, _bit: bool) -> Result<(), Self::Error> {
+    Ok(())
+  }
+
+  fn smbus_read_block_data(
+    &mut self,
+    _register: u8,
+  ) -> Result<Vec<u8>, Self::Error> {
+    Err(unsupported("smbus_read_block_data"))
+  }
+
+  fn smbus_read_i2c_block_data(
+    &mut self,
+    register: u8,
+    len: u8,
+  ) -> Result<Vec<u8>, Self::Error> {
+    self.write(&[register])?;
+    let mut data = vec![0; len as usize];
+    self.read(&mut data)?;
+    Ok(data)
+  }
+
+  fn smbus_write_block_data(
+    &mut self,
+    _register: u8,
+    _values: &[u8],
+  ) -> Result<(), Self::Error> {
+    Err(unsupported("smbus_write_block_data"))
+  }
+
+  fn smbus_write_i2c_block_data(
+    &mut self,
+    register: u8,
+    values: &[u8],
+  ) -> Result<(), Self::Error> {
+    let mut data = Vec::with_capacity(values.len() + 1);
+    data.push(register);
+    data.extend_from_slice(values);
+    self.write(&data)
+  }
+
+  fn smbus_process_block(
+    &mut self,
+    _register: u8,
+    _values: &[u8],
+  ) -> Result<Vec<u8>, Self::Error> {
+    Err(unsupported("smbus_process_block"))
+  }
+}
+
+fn unsupported(operation: &str) -> LinuxI2CError {
+  LinuxI2CError::Io(std::io::Error::new(
+    std::io::ErrorKind::Unsupported,
+    format!("{} is not supported by MockI2c", operation),
+  ))
+}
@@ -0,0 +1,30 @@
+//! Delays required by the IC between operations.
+//!
+//! Values marked as spec minimums come from the [technical specs]; the rest
+//! are polling parameters chosen by this driver.
+//!
+//! [technical specs]: https://www.ti.com/lit/ds/symlink/lp55231.pdf
+
+use std::time::Duration;
+
+/// Delay after setting `CHIP_EN` for the internal oscillator to start up,
+/// before issuing any other command (spec minimum).
+pub const CHIP_EN_STARTUP: Duration = Duration::from_micros(500);
+
+/// Delay after entering load program mode before writing to program memory.
+///
+/// The spec requires at least 1ms (or polling `ENGINE_BUSY`); both are done,
+/// with extra margin on the delay.
+pub const LOAD_MODE_DELAY: Duration = Duration::from_millis(10);
+
+/// Interval between reads of `STARTUP_BUSY` after a reset.
+pub const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Time allowed for `STARTUP_BUSY` to clear after a reset.
+pub const STARTUP_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Interval between reads of `ENGINE_BUSY`.
+pub const ENGINE_BUSY_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Time allowed for `ENGINE_BUSY` to clear.
+pub const ENGINE_BUSY_TIMEOUT: Duration = Duration::from_secs(1);
@@ -1,5 +1,86 @@
-/// Output channels.
+use std::fmt;
+
+use linux_embedded_hal::i2cdev::linux::LinuxI2CError;
+
+use crate::DecodedInstruction;
+
+/// Error converting an out of range value into an enum.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InvalidValue(pub u8);
+
+impl fmt::Display for InvalidValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid value {}", self.0)
+  }
+}
+
+impl std::error::Error for InvalidValue {}
+
+/// Error for block transfers attempted while register address
+/// auto-increment (`EN_AUTO_INCR`) is disabled, in which case every byte would
+/// access the same register.
+///
+/// Returned wrapped in [`LinuxI2CError::Io`]; see
+/// [`LP55231::set_auto_increment`](crate::LP55231::set_auto_increment).
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AutoIncrementRequired;
+
+impl fmt::Display for AutoIncrementRequired {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "block transfer requires auto-increment (EN_AUTO_INCR)")
+  }
+}
+
+impl std::error::Error for AutoIncrementRequired {}
+
+/// Error verifying a loaded program (see
+/// [`LP55231::verify_program`](crate::LP55231::verify_program)).
+#[derive(Debug)]
+pub enum VerifyError {
+  /// Instruction at `index` in program memory differs from the expected one.
+  Mismatch {
+    index: usize,
+    expected: DecodedInstruction,
+    actual: DecodedInstruction,
+  },
+  /// Reading program memory failed.
+  I2c(LinuxI2CError),
+}
+
+impl fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Mismatch {
+        index,
+        expected,
+        actual,
+      } => write!(
+        f,
+        "instruction {} mismatch; expecting {:?} but got {:?}",
+        index, expected, actual
+      ),
+      Self::I2c(error) => write!(f, "{}", error),
+    }
+  }
+}
+
+impl std::error::Error for VerifyError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Mismatch { .. } => None,
+      Self::I2c(error) => Some(error),
+    }
+  }
+}
+
+impl From<LinuxI2CError> for VerifyError {
+  fn from(error: LinuxI2CError) -> Self {
+    Self::I2c(error)
+  }
+}
+
+/// Output channels.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Channel {
   D1 = 0,
   D2,
@@ -12,31 +93,139 @@ pub enum Channel {
   D9,
 }
 
-/// Master faders.
+impl Channel {
+  /// All channels, ordered D1 through D9.
+  pub fn all() -> [Channel; 9] {
+    [
+      Channel::D1,
+      Channel::D2,
+      Channel::D3,
+      Channel::D4,
+      Channel::D5,
+      Channel::D6,
+      Channel::D7,
+      Channel::D8,
+      Channel::D9,
+    ]
+  }
+
+  /// Zero-based index of the channel (D1 is 0, D9 is 8).
+  pub fn as_index(&self) -> usize {
+    *self as usize
+  }
+}
+
+impl TryFrom<u8> for Channel {
+  type Error = InvalidValue;
+
+  /// Convert a zero-based index (0 for D1 through 8 for D9) to a [Channel].
+  fn try_from(value: u8) -> Result<Self, Self::Error> {
+    Channel::all()
+      .get(value as usize)
+      .copied()
+      .ok_or(InvalidValue(value))
+  }
+}
+
+/// Three output channels wired to the red, green and blue dies of an RGB LED.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RgbGroup {
+  pub r: Channel,
+  pub g: Channel,
+  pub b: Channel,
+}
+
+/// Master faders.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Fader {
   F1 = 0,
   F2,
   F3,
 }
 
+impl Fader {
+  /// All faders, ordered F1 through F3.
+  pub fn all() -> [Fader; 3] {
+    [Fader::F1, Fader::F2, Fader::F3]
+  }
+}
+
+impl TryFrom<u8> for Fader {
+  type Error = InvalidValue;
+
+  /// Convert a zero-based index (0 for F1 through 2 for F3) to a [Fader].
+  fn try_from(value: u8) -> Result<Self, Self::Error> {
+    Fader::all()
+      .get(value as usize)
+      .copied()
+      .ok_or(InvalidValue(value))
+  }
+}
+
 /// Programming engines.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Engine {
   E1 = 0,
   E2,
   E3,
 }
 
+impl Engine {
+  /// All engines, ordered E1 through E3.
+  pub fn all() -> [Engine; 3] {
+    [Engine::E1, Engine::E2, Engine::E3]
+  }
+}
+
+impl TryFrom<u8> for Engine {
+  type Error = InvalidValue;
+
+  /// Convert a zero-based index (0 for E1 through 2 for E3) to an [Engine].
+  fn try_from(value: u8) -> Result<Self, Self::Error> {
+    Engine::all()
+      .get(value as usize)
+      .copied()
+      .ok_or(InvalidValue(value))
+  }
+}
+
 /// Engine execution control modes.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum EngineExec {
   Hold = 0,
   Step,
   Free,
+  /// Execute the instruction at the program counter, without incrementing
+  /// it, then return to [`Hold`](Self::Hold).
   ExecuteOnce,
 }
 
+impl From<u8> for EngineExec {
+  fn from(value: u8) -> Self {
+    match value {
+      0b00 => Self::Hold,
+      0b01 => Self::Step,
+      0b10 => Self::Free,
+      0b11 => Self::ExecuteOnce,
+      _ => panic!("invalid value for EngineExec {:b}", value),
+    }
+  }
+}
+
+impl EngineExec {
+  /// Raw 2-bit value, as stored in `ENABLE / ENGINE CNTRL1`.
+  pub fn bits(&self) -> u8 {
+    *self as u8
+  }
+}
+
+impl fmt::Display for EngineExec {
+  /// Formats as name and raw value, e.g. `Free (0b10)`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} (0b{:02b})", self, self.bits())
+  }
+}
+
 /// Engine modes (i.e. state).
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum EngineMode {
@@ -46,12 +235,46 @@ pub enum EngineMode {
   Halt,
 }
 
+impl From<u8> for EngineMode {
+  fn from(value: u8) -> Self {
+    match value {
+      0b00 => Self::Disabled,
+      0b01 => Self::LoadProgram,
+      0b10 => Self::RunProgram,
+      0b11 => Self::Halt,
+      _ => panic!("invalid value for EngineMode {:b}", value),
+    }
+  }
+}
+
+impl EngineMode {
+  /// Raw 2-bit value, as stored in `ENGINE CNTRL2`.
+  pub fn bits(&self) -> u8 {
+    *self as u8
+  }
+}
+
+impl fmt::Display for EngineMode {
+  /// Formats as name and raw value, e.g. `RunProgram (0b10)`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} (0b{:02b})", self, self.bits())
+  }
+}
+
 /// Charge pump modes.
+///
+/// `CP_MODE` is the only charge pump setting exposed by the IC; in particular,
+/// the thresholds at which [`Auto`](Self::Auto) switches gain are fixed.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ChargePumpMode {
+  /// Charge pump disabled.
   Off = 0,
+  /// Outputs powered straight from the supply (1x gain).
   Bypass,
+  /// Outputs always powered through the charge pump (1.5x gain).
   Boosted,
+  /// Gain switched between 1x and 1.5x by the IC, based on the headroom
+  /// required by the outputs.
   Auto,
 }
 
@@ -102,3 +325,185 @@ pub struct Misc {
   /// CLK_DET_EN and INT_CLK_EN
   pub clock_selection: ClockSelection,
 }
+
+impl Default for Misc {
+  /// Power-on defaults of the IC (`MISC` reads `0x00` after a reset).
+  fn default() -> Self {
+    Self {
+      auto_increment_enabled: false,
+      powersave_enabled: false,
+      charge_pump_mode: ChargePumpMode::Off,
+      pwm_powersave_enabled: false,
+      clock_selection: ClockSelection::ForceExternal,
+    }
+  }
+}
+
+impl Misc {
+  /// See [`Misc::auto_increment_enabled`].
+  pub fn with_auto_increment(mut self, enabled: bool) -> Self {
+    self.auto_increment_enabled = enabled;
+    self
+  }
+
+  /// See [`Misc::powersave_enabled`].
+  pub fn with_powersave(mut self, enabled: bool) -> Self {
+    self.powersave_enabled = enabled;
+    self
+  }
+
+  /// See [`Misc::charge_pump_mode`].
+  pub fn with_charge_pump_mode(mut self, mode: ChargePumpMode) -> Self {
+    self.charge_pump_mode = mode;
+    self
+  }
+
+  /// See [`Misc::pwm_powersave_enabled`].
+  pub fn with_pwm_powersave(mut self, enabled: bool) -> Self {
+    self.pwm_powersave_enabled = enabled;
+    self
+  }
+
+  /// See [`Misc::clock_selection`].
+  pub fn with_clock_selection(mut self, selection: ClockSelection) -> Self {
+    self.clock_selection = selection;
+    self
+  }
+
+  /// Names of the fields whose values differ between `self` and `other`, in
+  /// declaration order; empty if both are equal.
+  ///
+  /// Useful to explain why [misc](Misc) settings read back from the IC
+  /// don't match the ones written.
+  pub fn diff(&self, other: &Misc) -> Vec<&'static str> {
+    let mut fields = vec![];
+    if self.auto_increment_enabled != other.auto_increment_enabled {
+      fields.push("auto_increment_enabled");
+    }
+    if self.powersave_enabled != other.powersave_enabled {
+      fields.push("powersave_enabled");
+    }
+    if self.charge_pump_mode != other.charge_pump_mode {
+      fields.push("charge_pump_mode");
+    }
+    if self.pwm_powersave_enabled != other.pwm_powersave_enabled {
+      fields.push("pwm_powersave_enabled");
+    }
+    if self.clock_selection != other.clock_selection {
+      fields.push("clock_selection");
+    }
+    fields
+  }
+}
+
+/// INT/GPO pin configuration.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct IntGpo {
+  /// INT_CONF; when set, the INT pin acts as a general purpose output instead
+  /// of an interrupt output.
+  pub int_as_gpo: bool,
+  /// GPO; state of the GPO pin.
+  pub gpo_high: bool,
+  /// INT_GPO; state of the INT pin when acting as a general purpose output.
+  pub int_gpo_high: bool,
+}
+
+/// Temperature sensor configuration, as stored in `TEMP ADC CONTROL`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TempAdcControl {
+  /// TEMP_MEAS_BUSY; set by the IC while a measurement is in progress.
+  pub measurement_busy: bool,
+  /// EN_TEMP_SENSOR
+  pub sensor_enabled: bool,
+  /// CONTINUOUS_CONV; when set, the temperature is measured continuously
+  /// rather than once.
+  pub continuous_conversion: bool,
+  /// SEL_EXT_TEMP; when set, the temperature written to `TEMPERATURE WRITE`
+  /// is used instead of the internal sensor's reading.
+  pub external_temperature: bool,
+}
+
+/// Status and interrupt flags.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Status {
+  /// LEDTEST_MEAS_DONE
+  pub ledtest_measurement_done: bool,
+  /// MASK_BUSY
+  pub mask_busy: bool,
+  /// STARTUP_BUSY
+  pub startup_busy: bool,
+  /// ENGINE_BUSY
+  pub engine_busy: bool,
+  /// EXT_CLK_USED
+  pub ext_clk_used: bool,
+  /// ENG1_INT
+  pub engine1_interrupt: bool,
+  /// ENG2_INT
+  pub engine2_interrupt: bool,
+  /// ENG3_INT
+  pub engine3_interrupt: bool,
+}
+
+/// Execution control and mode of every programming [`Engine`], as stored in
+/// `ENABLE / ENGINE CNTRL1` and `ENGINE CNTRL2`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EngineControl {
+  /// ENGINE1_EXEC
+  pub engine1_exec: EngineExec,
+  /// ENGINE2_EXEC
+  pub engine2_exec: EngineExec,
+  /// ENGINE3_EXEC
+  pub engine3_exec: EngineExec,
+  /// ENGINE1_MODE
+  pub engine1_mode: EngineMode,
+  /// ENGINE2_MODE
+  pub engine2_mode: EngineMode,
+  /// ENGINE3_MODE
+  pub engine3_mode: EngineMode,
+}
+
+/// State of a single output [`Channel`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChannelState {
+  pub channel: Channel,
+  /// Dx PWM
+  pub pwm: u8,
+  /// Dx CURRENT CONTROL
+  pub current: u8,
+  /// Dx_ON
+  pub enabled: bool,
+  /// LOG_EN
+  pub log_brightness: bool,
+  /// Dx_RATIO_EN
+  pub ratiometric_dimming: bool,
+  /// MAPPING
+  pub fader_assignment: Option<Fader>,
+}
+
+/// State of a single programming [`Engine`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EngineState {
+  pub engine: Engine,
+  /// ENGINEx_MODE
+  pub mode: EngineMode,
+  /// ENGINEx_EXEC
+  pub exec: EngineExec,
+  /// ENGINEx PC
+  pub program_counter: u8,
+  /// ENGx PROG START ADDR
+  pub entry_point: u8,
+}
+
+/// Snapshot of the whole chip state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChipState {
+  /// CHIP_EN
+  pub enabled: bool,
+  pub misc: Misc,
+  /// Per-channel state, ordered D1 through D9.
+  pub channels: Vec<ChannelState>,
+  /// Per-engine state, ordered E1 through E3.
+  pub engines: Vec<EngineState>,
+  /// Raw STATUS/INTERRUPT byte.
+  pub status: u8,
+}